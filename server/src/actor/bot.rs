@@ -0,0 +1,194 @@
+use crate::{
+    event::{
+        lobby::{BotSkill, GameSettings},
+        player::{PlayerEvent, PlayerRespondsWithId},
+    },
+    router::lobby::{LobbyEvent, LobbyPlayer, LobbyStart},
+    zzz::bot::Bot,
+};
+use arcstr::ArcStr;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, error::SendError},
+    },
+    time::{sleep_until, Instant},
+};
+use tracing::{error, info, instrument, trace, warn};
+use triomphe::Arc;
+
+/// The subset of the game events that bots care about.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum GameView {
+    GameExpected {
+        token: usize,
+        next: usize,
+        action: ArcStr,
+    },
+    GameEliminated {
+        pid: usize,
+    },
+    GameLifeLost {
+        pid: usize,
+    },
+    GamePaused,
+    #[serde(other)]
+    Other,
+}
+
+/// Keeps track of the players in the lobby. Returns `false` if the bot should leave, i.e., when the bot itself has
+/// been removed or when there are no more human players to play with.
+fn track_lobby_event(roster: &mut BTreeMap<usize, LobbyPlayer>, pid: usize, event: LobbyEvent) -> bool {
+    match event {
+        LobbyEvent::PlayerJoined(event) => {
+            // Players are announced in the order in which they join
+            let joined = roster.values().map(|player| player.joined + 1).max().unwrap_or_default();
+            let player = LobbyPlayer {
+                name: event.player,
+                team: event.team,
+                wins: event.wins,
+                joined,
+                bot: event.bot,
+                token: None,
+                kicked: None,
+            };
+            roster.insert(event.pid, player);
+        }
+        LobbyEvent::PlayerLeft(event) if event.pid == pid => {
+            info!(?event.reason, "bot has been removed from the lobby");
+            return false;
+        }
+        LobbyEvent::PlayerLeft(event) => {
+            roster.remove(&event.pid);
+            if roster.values().all(|player| player.bot) {
+                info!("no more human players left in the lobby");
+                return false;
+            }
+        }
+        LobbyEvent::Dissolved(event) => {
+            info!(?event.reason, "lobby has been dissolved");
+            return false;
+        }
+        LobbyEvent::Start(_) | LobbyEvent::HostChanged(_) | LobbyEvent::Rejected(_) | LobbyEvent::Reopened(_) => (),
+    }
+    true
+}
+
+async fn wait_for_game_start(
+    lobby_rx: &mut broadcast::Receiver<LobbyEvent>,
+    roster: &mut BTreeMap<usize, LobbyPlayer>,
+    pid: usize,
+) -> Option<LobbyStart> {
+    loop {
+        match lobby_rx.recv().await {
+            Ok(LobbyEvent::Start(start)) => return Some(start),
+            Ok(event) => {
+                if !track_lobby_event(roster, pid, event) {
+                    return None;
+                }
+            }
+            Err(RecvError::Lagged(count)) => warn!(count, "bot lagged behind the lobby events"),
+            Err(RecvError::Closed) => {
+                info!("lobby has expired");
+                return None;
+            }
+        }
+    }
+}
+
+/// Plays a single game on behalf of the bot. Returns `false` if the bot should leave the lobby instead.
+async fn play_game(
+    lobby_rx: &mut broadcast::Receiver<LobbyEvent>,
+    broadcast_rx: &mut broadcast::Receiver<Arc<[u8]>>,
+    event_tx: &mpsc::Sender<PlayerEvent>,
+    roster: &mut BTreeMap<usize, LobbyPlayer>,
+    bot: &mut Bot,
+    pid: usize,
+) -> bool {
+    // Potatoes that the bot is about to pass on
+    let mut pending: Vec<(Instant, usize, ArcStr)> = Vec::new();
+    let leave = loop {
+        let due = pending.iter().enumerate().min_by_key(|&(_, &(due, ..))| due).map(|(index, &(due, ..))| (index, due));
+        let sleep = sleep_until(due.map_or_else(Instant::now, |(_, due)| due));
+        tokio::select! {
+            biased;
+            event = lobby_rx.recv() => match event {
+                Ok(event) => {
+                    if !track_lobby_event(roster, pid, event) {
+                        break true;
+                    }
+                }
+                Err(RecvError::Lagged(count)) => warn!(count, "bot lagged behind the lobby events"),
+                Err(RecvError::Closed) => break true,
+            },
+            bytes = broadcast_rx.recv() => match bytes {
+                Ok(bytes) => match rmp_serde::from_slice(&bytes) {
+                    Ok(GameView::GameExpected { token, next, action }) => {
+                        bot.observe(token, next);
+                        pending.retain(|&(_, other, _)| other != token);
+                        if next == pid {
+                            pending.push((Instant::now() + bot.reaction_time(), token, action));
+                        }
+                    }
+                    Ok(GameView::GameEliminated { pid: other }) => bot.eliminate(other),
+                    Ok(GameView::GameLifeLost { pid: other }) => bot.lose_life(other),
+                    // Every potato is announced again once the game resumes
+                    Ok(GameView::GamePaused) => pending.clear(),
+                    Ok(GameView::Other) => (),
+                    Err(err) => error!(?err, "cannot deserialize game event"),
+                },
+                Err(RecvError::Lagged(count)) => warn!(count, "bot lagged behind the game events"),
+                Err(RecvError::Closed) => break false,
+            },
+            () = sleep, if due.is_some() => {
+                let (index, _) = due.unwrap();
+                let (_, token, action) = pending.swap_remove(index);
+                let data = bot.respond(token, action);
+                trace!(?data, "bot responds");
+                if let Err(SendError(event)) = event_tx.send(PlayerEvent::Responds(PlayerRespondsWithId { pid, data })).await {
+                    warn!(?event, "game has already concluded");
+                }
+            }
+        }
+    };
+
+    if leave {
+        if let Err(SendError(event)) = event_tx.send(PlayerEvent::Forfeit(pid)).await {
+            warn!(?event, "game has already concluded");
+        }
+        return false;
+    }
+
+    info!("game has gracefully concluded");
+    true
+}
+
+/// Drives a bot player through every game of the lobby until the bot is removed or no human players are left. The
+/// bot takes part in the game through the same channels as any other player.
+#[instrument(skip(lobby_rx, settings, roster))]
+pub async fn bot_actor(
+    mut lobby_rx: broadcast::Receiver<LobbyEvent>,
+    pid: usize,
+    skill: BotSkill,
+    settings: GameSettings,
+    mut roster: BTreeMap<usize, LobbyPlayer>,
+) {
+    while let Some(start) = wait_for_game_start(&mut lobby_rx, &mut roster, pid).await {
+        let LobbyStart { ready_tx, event_tx, mut broadcast_rx, .. } = start;
+
+        // Bots are always ready to play
+        drop(ready_tx);
+
+        let mut players: Vec<_> = roster.iter().map(|(&pid, player)| (player.joined, pid, player.team)).collect();
+        players.sort_unstable();
+        let players = players.into_iter().map(|(_, pid, team)| (pid, team)).collect();
+        let mut bot = Bot::new(pid, skill, players, &settings, rand::random());
+        if !play_game(&mut lobby_rx, &mut broadcast_rx, &event_tx, &mut roster, &mut bot, pid).await {
+            break;
+        }
+    }
+    info!("bot has left the lobby");
+}
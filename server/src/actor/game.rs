@@ -1,34 +1,253 @@
 use crate::{
     event::{
-        game::{GameConcluded, GameEliminated, GameExpected},
-        player::{PlayerResponds, PlayerRespondsWithId},
+        game::{
+            EliminationReason, GameCommand, GameConcluded, GameEliminated, GameLifeLost, GamePaused, GameResumed,
+            GameSpecialUsed, GameSuddenDeath,
+        },
+        player::PlayerEvent,
         Event,
     },
-    zzz::{GameWinnerError, TickResult, ZipZapZop},
+    router::game::GameProgress,
+    zzz::{
+        deadline::DeadlinePolicy, record::GameRecord, sudden_death::SuddenDeath, GameWinnerError, TickResult, Winner,
+        ZipZapZop,
+    },
 };
+use arcstr::ArcStr;
 use core::{fmt::Debug, time::Duration};
 use jiff::Timestamp;
 use tokio::{
     sync::{
         broadcast::{error::SendError, Sender},
         mpsc::Receiver,
+        watch,
     },
-    time::timeout,
+    time::{timeout_at, Instant},
 };
 use tracing::{error, info, info_span, instrument, trace, warn};
 use triomphe::Arc;
 
-#[instrument(skip(broadcast_tx, event_rx))]
-async fn handle_game_tick<Player: Debug>(
+/// The turn of the current holder of a potato.
+#[derive(Clone, Copy, Debug)]
+struct Turn {
+    /// When the holder was announced.
+    since: Instant,
+    /// When the holder times out.
+    until: Instant,
+    /// When the holder times out as announced to the players.
+    deadline: Timestamp,
+    /// The player who held the potato when the turn was announced.
+    holder: usize,
+}
+
+/// Announces the current holder of the `token` to everyone, who must respond within the `duration`.
+fn expect_token<Player>(
+    broadcast_tx: &Sender<Arc<[u8]>>,
+    zzz: &ZipZapZop<Player>,
+    duration: Duration,
+    token: usize,
+) -> Result<Turn, Arc<[u8]>> {
+    let deadline = Timestamp::now().saturating_add(duration).unwrap();
+    let bytes = rmp_serde::to_vec_named(&Event::from(zzz.expects(token, deadline))).unwrap().into();
+    let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+    trace!(count, token, "broadcasted game event");
+    let since = Instant::now();
+    Ok(Turn { since, until: since + duration, deadline, holder: zzz.holder(token) })
+}
+
+/// Discards the turns of the potatoes that the penalized player held or that have since changed hands, so that only
+/// these are announced anew. The other holders keep their deadlines.
+fn restart_turns<Player>(zzz: &ZipZapZop<Player>, turns: &mut [Option<Turn>], pid: usize) {
+    for (token, turn) in turns.iter_mut().enumerate() {
+        if turn.is_some_and(|Turn { holder, .. }| zzz.is_restarted(token, holder, pid)) {
+            *turn = None;
+        }
+    }
+}
+
+/// Publishes the current state of the game for anyone who joins in the middle of it (or falls behind).
+fn publish_progress<Player: Clone + Into<ArcStr>>(
+    progress_tx: &watch::Sender<GameProgress>,
+    zzz: &ZipZapZop<Player>,
+    record: &GameRecord,
+    turns: &[Option<Turn>],
+    paused: bool,
+) {
+    let deadlines: Vec<_> = turns.iter().map(|turn| turn.map(|Turn { deadline, .. }| deadline)).collect();
+    let snapshot = zzz.snapshot(&deadlines, record.total_rounds, paused);
+    let bytes = rmp_serde::to_vec_named(&Event::from(snapshot)).unwrap().into();
+    let remaining = zzz.survivors().collect();
+    progress_tx.send_replace(GameProgress { remaining, round: record.total_rounds, snapshot: Some(bytes) });
+}
+
+/// Eliminates every survivor except for the fastest responder (and their teammates), slowest first.
+fn declare_by_tiebreak<Player: Debug>(
     broadcast_tx: &Sender<Arc<[u8]>>,
-    event_rx: &mut Receiver<PlayerRespondsWithId>,
     zzz: &mut ZipZapZop<Player>,
-    round: &mut u32,
+    record: &mut GameRecord,
+) -> Result<(), Arc<[u8]>> {
+    let ranking = record.tiebreak(zzz.survivors());
+    let Some((&fastest, rest)) = ranking.split_first() else {
+        return Ok(());
+    };
+
+    let team = zzz.team(fastest);
+    info!(fastest, ?team, "declaring the winner by tiebreak");
+    for &pid in rest.iter().rev() {
+        if team.is_some() && zzz.team(pid) == team {
+            continue;
+        }
+        let player = zzz.disqualify(pid);
+        info!(pid, ?player, "player eliminated by tiebreak");
+        let bytes = rmp_serde::to_vec_named(&Event::from(GameEliminated { pid, reason: EliminationReason::Tiebreak }))
+            .unwrap()
+            .into();
+        let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+        trace!(count, "broadcasted game event");
+        record.eliminate(pid, EliminationReason::Tiebreak, Timestamp::now());
+    }
+
+    Ok(())
+}
+
+/// Broadcasts the outcome of a game tick and updates the pacing of the game accordingly.
+fn apply_tick_result<Player: Debug>(
+    broadcast_tx: &Sender<Arc<[u8]>>,
+    zzz: &ZipZapZop<Player>,
+    record: &mut GameRecord,
+    turns: &mut [Option<Turn>],
+    pid: usize,
+    token: usize,
+    result: TickResult<Player>,
+) -> Result<(), Arc<[u8]>> {
+    let span = info_span!("player-event", pid, token);
+    let _guard = span.enter();
+
+    match result {
+        TickResult::NoOp => info!("game state no-op transition"),
+        TickResult::Proceed => {
+            info!("game state successfully transitioned");
+            if let Some(Turn { since, .. }) = turns[token].take() {
+                record.respond(pid, since.elapsed());
+            }
+            record.proceed();
+        }
+        TickResult::Special { special, next, remaining } => {
+            info!(?special, next, remaining, "player played a special action");
+            let event = GameSpecialUsed { pid, token, special, next, remaining };
+            let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap().into();
+            let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+            trace!(count, "broadcasted game event");
+            if let Some(Turn { since, .. }) = turns[token].take() {
+                record.respond(pid, since.elapsed());
+            }
+            record.proceed();
+        }
+        TickResult::Eliminated(player, reason) => {
+            info!(?player, ?reason, "player eliminated");
+            let event = GameEliminated { pid, reason: reason.clone() };
+            let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap().into();
+            let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+            trace!(count, "broadcasted game event");
+            record.eliminate(pid, reason, Timestamp::now());
+            restart_turns(zzz, turns, pid);
+        }
+        TickResult::LifeLost { reason, remaining } => {
+            info!(?reason, remaining, "player lost a life");
+            let event = GameLifeLost { pid, reason, remaining };
+            let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap().into();
+            let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+            trace!(count, "broadcasted game event");
+            record.round = 0; // restart the pacing after every mistake
+            restart_turns(zzz, turns, pid);
+        }
+    }
+
+    Ok(())
+}
+
+/// Freezes the game until the host resumes it. Player responses are rejected without elimination in the meantime,
+/// but disconnections are still processed.
+async fn pause_game<Player: Clone + Debug + Into<ArcStr>>(
+    broadcast_tx: &Sender<Arc<[u8]>>,
+    progress_tx: &watch::Sender<GameProgress>,
+    event_rx: &mut Receiver<PlayerEvent>,
+    command_rx: &mut Receiver<GameCommand>,
+    zzz: &mut ZipZapZop<Player>,
+    record: &mut GameRecord,
+    turns: &mut [Option<Turn>],
+) -> Result<bool, Arc<[u8]>> {
+    info!("game paused by host");
+    let bytes = rmp_serde::to_vec_named(&Event::from(GamePaused)).unwrap().into();
+    let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+    trace!(count, "broadcasted game event");
+    publish_progress(progress_tx, zzz, record, turns, true);
+
+    loop {
+        tokio::select! {
+            biased;
+            command = command_rx.recv() => match command {
+                Some(GameCommand::GameResume) => break,
+                Some(GameCommand::GamePause) => warn!("game is already paused"),
+                None => {
+                    warn!("host has left the paused game - resuming");
+                    break;
+                }
+            },
+            event = event_rx.recv() => match event {
+                Some(PlayerEvent::Forfeit(pid)) => {
+                    let result = zzz.forfeit(pid);
+                    apply_tick_result(broadcast_tx, zzz, record, turns, pid, 0, result)?;
+                    publish_progress(progress_tx, zzz, record, turns, true);
+                }
+                Some(PlayerEvent::Responds(event)) => {
+                    warn!(?event, "player response rejected while the game is paused");
+                }
+                None => {
+                    error!("all players have left the game");
+                    return Ok(false);
+                }
+            },
+        }
+    }
+
+    info!("game resumed by host");
+    let bytes = rmp_serde::to_vec_named(&Event::from(GameResumed)).unwrap().into();
+    let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+    trace!(count, "broadcasted game event");
+
+    // Every potato is announced again with a fresh deadline
+    turns.fill(None);
+    Ok(true)
+}
+
+/// Handles a single player event (or timeout). Every potato whose entry in `turns` is `None` is announced anew
+/// before waiting for the next event.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(broadcast_tx, progress_tx, event_rx, command_rx, policy, record, turns, sudden_death))]
+async fn handle_game_tick<Player: Clone + Debug + Into<ArcStr>, Policy: DeadlinePolicy + ?Sized>(
+    broadcast_tx: &Sender<Arc<[u8]>>,
+    progress_tx: &watch::Sender<GameProgress>,
+    event_rx: &mut Receiver<PlayerEvent>,
+    command_rx: &mut Receiver<GameCommand>,
+    zzz: &mut ZipZapZop<Player>,
+    policy: &Policy,
+    record: &mut GameRecord,
+    turns: &mut [Option<Turn>],
+    sudden_death: &mut SuddenDeath,
 ) -> Result<bool, Arc<[u8]>> {
     match zzz.winner() {
-        Ok((pid, player)) => {
-            info!(pid, ?player, "game concluded with winner");
-            let bytes = rmp_serde::to_vec_named(&Event::from(GameConcluded { pid })).unwrap().into();
+        Ok(winner) => {
+            info!(?winner, "game concluded with winner");
+            let (pid, team) = match winner {
+                Winner::Player(pid) => (Some(pid), None),
+                Winner::Team(team) => (None, Some(team)),
+            };
+            // Hand the players back to the lobby before they may react to the conclusion
+            event_rx.close();
+            let winners: Vec<_> = zzz.survivors().collect();
+            let standings = record.standings(&winners);
+            let bytes = rmp_serde::to_vec_named(&Event::from(GameConcluded { pid, team, standings })).unwrap().into();
             let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
             trace!(count, "broadcasted game event");
             return Ok(false);
@@ -40,66 +259,99 @@ async fn handle_game_tick<Player: Debug>(
         Err(GameWinnerError::MorePlayers) => (),
     }
 
-    let secs = 6.0 * (-f64::from(*round) / 16.0).exp();
-    let duration = Duration::from_secs_f64(secs);
-    let deadline = Timestamp::now().saturating_add(duration).unwrap();
+    if sudden_death.is_over(record.total_rounds) {
+        declare_by_tiebreak(broadcast_tx, zzz, record)?;
+        return Ok(true);
+    }
 
-    let expects = zzz.expects(deadline);
-    let bytes = rmp_serde::to_vec_named(&Event::from(expects)).unwrap().into();
-    let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
-    trace!(count, "broadcasted game event");
+    if sudden_death.begin(record.total_rounds, Instant::now().into_std()) {
+        warn!(total_rounds = record.total_rounds, "game entered sudden death");
+        let SuddenDeath { deadline, rounds, .. } = *sudden_death;
+        let event = GameSuddenDeath { deadline: deadline.as_secs_f64(), rounds };
+        let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap().into();
+        let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
+        trace!(count, "broadcasted game event");
+        turns.fill(None);
+    }
 
-    loop {
-        let event = match timeout(duration, event_rx.recv()).await {
-            Ok(Some(event)) => event,
-            Ok(None) => {
-                error!("all players have left the game");
-                return Ok(false);
-            }
-            Err(err) => {
-                warn!(?err, "round timeout elapsed - gracefully eliminating current player");
-                let GameExpected { next, action, .. } = expects;
-                PlayerRespondsWithId { pid: next, data: PlayerResponds { next, action } }
-            }
-        };
+    let duration = if sudden_death.is_active() {
+        sudden_death.deadline
+    } else {
+        policy.deadline(record.round, zzz.remaining(), &record.history)
+    };
+    for (token, turn) in turns.iter_mut().enumerate() {
+        if turn.is_none() {
+            *turn = Some(expect_token(broadcast_tx, zzz, duration, token)?);
+        }
+    }
+    publish_progress(progress_tx, zzz, record, turns, false);
 
-        let span = info_span!("player-event", ?event);
-        let _guard = span.enter();
+    let (next, Turn { until, .. }) = turns
+        .iter()
+        .enumerate()
+        .filter_map(|(token, turn)| turn.map(|turn| (token, turn)))
+        .min_by_key(|&(_, Turn { until, .. })| until)
+        .expect("at least one potato must be in play");
 
-        let pid = event.pid;
-        match zzz.tick(event) {
-            TickResult::NoOp => {
-                info!("game state no-op transition");
-                continue;
-            }
-            TickResult::Proceed => {
-                info!("game state successfully transitioned");
-                *round += 1; // increment per successful transition
-                break;
-            }
-            TickResult::Eliminated(player) => {
-                let bytes = rmp_serde::to_vec_named(&Event::from(GameEliminated { pid })).unwrap().into();
-                let count = broadcast_tx.send(bytes).map_err(|SendError(bytes)| bytes)?;
-                trace!(count, "broadcasted game event");
-                info!(?player, "player eliminated");
-                *round = 0; // reset per elimination
-                break;
+    let received = tokio::select! {
+        biased;
+        Some(command) = command_rx.recv() => {
+            if command == GameCommand::GameResume {
+                warn!("game is not paused");
+                return Ok(true);
             }
+            let paused_at = Instant::now();
+            let running = pause_game(broadcast_tx, progress_tx, event_rx, command_rx, zzz, record, turns).await?;
+            sudden_death.postpone(paused_at.elapsed());
+            return Ok(running);
         }
-    }
+        received = timeout_at(until, event_rx.recv()) => received,
+    };
+
+    let (pid, token, result) = match received {
+        Ok(Some(PlayerEvent::Responds(event))) => (event.pid, event.data.token, zzz.tick(event)),
+        Ok(Some(PlayerEvent::Forfeit(pid))) => (pid, 0, zzz.forfeit(pid)),
+        Ok(None) => {
+            error!("all players have left the game");
+            return Ok(false);
+        }
+        Err(err) => {
+            warn!(?err, token = next, "round timeout elapsed - eliminating current player");
+            (zzz.holder(next), next, zzz.timeout(next))
+        }
+    };
 
+    apply_tick_result(broadcast_tx, zzz, record, turns, pid, token, result)?;
     Ok::<_, Arc<[u8]>>(true)
 }
 
-#[instrument(skip(broadcast_tx, event_rx, zzz))]
-pub async fn handle_game<Player: Debug>(
-    event_rx: &mut Receiver<PlayerRespondsWithId>,
+/// Runs the game loop until the game concludes. The progress of the game is published through `progress_tx` whenever
+/// the potatoes have been announced (or the game has been paused).
+#[instrument(skip(broadcast_tx, progress_tx, event_rx, command_rx, zzz, policy))]
+pub async fn handle_game<Player: Clone + Debug + Into<ArcStr>, Policy: DeadlinePolicy + ?Sized>(
+    event_rx: &mut Receiver<PlayerEvent>,
+    command_rx: &mut Receiver<GameCommand>,
     broadcast_tx: &Sender<Arc<[u8]>>,
+    progress_tx: &watch::Sender<GameProgress>,
     zzz: &mut ZipZapZop<Player>,
+    policy: &Policy,
+    mut sudden_death: SuddenDeath,
 ) {
-    let mut round = 0;
+    let mut record = GameRecord::default();
+    let mut turns = vec![None; zzz.tokens()];
     loop {
-        match handle_game_tick(broadcast_tx, event_rx, zzz, &mut round).await {
+        let tick = handle_game_tick(
+            broadcast_tx,
+            progress_tx,
+            event_rx,
+            command_rx,
+            zzz,
+            policy,
+            &mut record,
+            &mut turns,
+            &mut sudden_death,
+        );
+        match tick.await {
             Ok(true) => continue,
             Ok(false) => break,
             Err(bytes) => {
@@ -108,4 +360,5 @@ pub async fn handle_game<Player: Debug>(
             }
         }
     }
+    event_rx.close();
 }
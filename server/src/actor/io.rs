@@ -1,86 +1,159 @@
 use crate::{
-    actor::send_fn,
-    event::player::{PlayerAction, PlayerResponds, PlayerRespondsWithId},
+    actor::{close_with_error, read_binary, ReadError},
+    event::{
+        error::ErrorCode,
+        game::GameCommand,
+        player::{PlayerEvent, PlayerResponds, PlayerRespondsWithId},
+    },
+    router::{game::GameProgress, lobby::is_valid_action},
 };
-use fastwebsockets::{FragmentCollectorRead, Frame, OpCode, Payload, WebSocketWrite};
+use fastwebsockets::{FragmentCollectorRead, Frame, Payload, WebSocketWrite};
+use serde::Deserialize;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     sync::{
         broadcast::{error::RecvError, Receiver},
-        mpsc::{error::SendError, Sender},
+        mpsc::{self, error::SendError, Sender},
+        watch,
     },
 };
 use tracing::{error, info, instrument, trace, warn};
 use triomphe::Arc;
 
-#[instrument(skip(event_tx, ws_reader))]
+/// Messages that players may send while the game is running.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GameMessage {
+    Responds(PlayerResponds),
+    Command(GameCommand),
+}
+
+/// How the player's participation in a game came to an end.
+pub enum GameExit {
+    /// The game concluded while the player was still connected. Carries the first message that the player sent
+    /// afterwards, which is meant for the reopened lobby instead.
+    Concluded(Result<Payload<'static>, ReadError>),
+    /// The player violated the protocol and forfeited the game.
+    Forfeited,
+    /// The connection dropped mid-game. The player has not forfeited yet, so that they may still resume.
+    Disconnected,
+}
+
+/// Since the [`WebSocketWrite`] half is owned by [`event_to_websocket_msgpack_actor`], protocol violations
+/// detected by the reader are relayed through `error_tx` so that they may be reported to the client. Game commands
+/// are only relayed through `command_tx` for the host who started the game. The game signals its conclusion by
+/// closing the receiving end of `event_tx`. A dropped connection is left for the caller to handle.
+#[instrument(skip(event_tx, command_tx, error_tx, ws_reader))]
 pub async fn websocket_msgpack_to_event_actor<Reader>(
     ws_reader: &mut FragmentCollectorRead<Reader>,
-    event_tx: &Sender<PlayerRespondsWithId>,
+    event_tx: &Sender<PlayerEvent>,
+    command_tx: Option<&Sender<GameCommand>>,
+    error_tx: &Sender<ErrorCode>,
     pid: usize,
-) where
+) -> GameExit
+where
     Reader: AsyncRead + Unpin,
 {
-    loop {
-        let payload = match ws_reader.read_frame(&mut send_fn).await {
-            Ok(Frame { fin: true, opcode: OpCode::Binary, payload, .. }) => payload,
-            Ok(Frame { fin, opcode, payload, .. }) => {
-                error!(fin, ?opcode, ?payload, "unexpected websocket frame received");
-                break;
-            }
-            Err(err) => {
-                error!(?err, "websocket reader error encountered");
-                break;
+    let code = loop {
+        let result = read_binary(ws_reader).await;
+        if event_tx.is_closed() {
+            // Late responses and commands are of no use to the lobby
+            if matches!(&result, Ok(payload) if rmp_serde::from_slice::<GameMessage>(payload).is_ok()) {
+                warn!("game message received after the game has concluded");
+                continue;
             }
+            info!("player is back in the lobby");
+            return GameExit::Concluded(result);
+        }
+
+        let payload = match result {
+            // Late round trip pings from the game start are harmless
+            Ok(payload) if payload.is_empty() => continue,
+            Ok(payload) => payload,
+            Err(ReadError::Disconnected) => return GameExit::Disconnected,
+            Err(ReadError::Malformed) => break ErrorCode::MalformedPayload,
         };
 
         let data = match rmp_serde::from_slice(&payload) {
-            Ok(event) => event,
+            Ok(GameMessage::Responds(data)) if !is_valid_action(&data.action) => {
+                error!(len = data.action.len(), "action is too long for any vocabulary");
+                break ErrorCode::MalformedPayload;
+            }
+            Ok(GameMessage::Responds(data)) => data,
+            Ok(GameMessage::Command(command)) => {
+                let Some(command_tx) = command_tx else {
+                    error!(?command, "only the host may control the game");
+                    break ErrorCode::NotHost;
+                };
+                if let Err(SendError(command)) = command_tx.send(command).await {
+                    warn!(?command, "game has already shut down");
+                }
+                continue;
+            }
             Err(err) => {
                 error!(?err, "cannot deserialize payload");
-                break;
+                break ErrorCode::MalformedPayload;
             }
         };
 
-        if let Err(SendError(event)) = event_tx.send(PlayerRespondsWithId { pid, data }).await {
-            warn!(?event, "lobby has already shut down");
-            return;
+        if let Err(SendError(event)) = event_tx.send(PlayerEvent::Responds(PlayerRespondsWithId { pid, data })).await {
+            warn!(?event, "game has already concluded");
         }
+    };
+
+    if let Err(SendError(code)) = error_tx.send(code).await {
+        warn!(?code, "websocket writer has already exited");
     }
 
     // Gracefully eliminate self from the lobby
-    if let Err(SendError(event)) =
-        event_tx.send(PlayerRespondsWithId { pid, data: PlayerResponds { next: pid, action: PlayerAction::Zip } }).await
-    {
-        error!(?event, "lobby has already shut down");
-        return;
+    if let Err(SendError(event)) = event_tx.send(PlayerEvent::Forfeit(pid)).await {
+        error!(?event, "game has already concluded");
     }
+
+    GameExit::Forfeited
 }
 
-#[instrument(skip(event_rx, ws_writer))]
+/// Returns `true` if the game has gracefully concluded, in which case the player may be handed back to the lobby.
+/// Clients who fall too far behind the game events skip ahead to the latest snapshot from `progress_rx`.
+#[instrument(skip(event_rx, progress_rx, error_rx, ws_writer))]
 pub async fn event_to_websocket_msgpack_actor<Writer>(
     ws_writer: &mut WebSocketWrite<Writer>,
     event_rx: &mut Receiver<Arc<[u8]>>,
-) where
+    progress_rx: &watch::Receiver<GameProgress>,
+    error_rx: &mut mpsc::Receiver<ErrorCode>,
+) -> bool
+where
     Writer: AsyncWrite + Unpin,
 {
     loop {
-        let bytes = match event_rx.recv().await {
-            Ok(bytes) => bytes,
-            Err(RecvError::Closed) => {
-                info!("lobby has gracefully exited");
-                break;
-            }
-            Err(RecvError::Lagged(count)) => {
-                error!(count, "broadcast receiver lagged");
-                break;
+        let bytes = tokio::select! {
+            biased;
+            Some(code) = error_rx.recv() => {
+                close_with_error(ws_writer, code).await;
+                return false;
             }
+            result = event_rx.recv() => match result {
+                Ok(bytes) => bytes,
+                Err(RecvError::Closed) => {
+                    info!("game has gracefully concluded");
+                    return true;
+                }
+                Err(RecvError::Lagged(count)) => {
+                    warn!(count, "broadcast receiver lagged - skipping ahead to the latest snapshot");
+                    // Events that are newer than the snapshot are still delivered afterwards
+                    *event_rx = event_rx.resubscribe();
+                    let Some(bytes) = progress_rx.borrow().snapshot.clone() else {
+                        continue;
+                    };
+                    bytes
+                }
+            },
         };
 
         let payload = Payload::Borrowed(&bytes);
         if let Err(err) = ws_writer.write_frame(Frame::binary(payload)).await {
             error!(?err, "websocket writer error encountered");
-            break;
+            return false;
         }
 
         trace!("delivered incoming event to the websocket");
@@ -0,0 +1,44 @@
+use hyper::body::Bytes;
+use tokio::sync::{
+    broadcast::{error::RecvError, Receiver},
+    mpsc::Sender,
+};
+use tracing::{error, info, instrument, trace};
+
+#[instrument(skip_all)]
+pub async fn lobby_listing_actor(sse_tx: &Sender<Bytes>, listing_rx: &mut Receiver<Bytes>, snapshot: Vec<Bytes>) {
+    for bytes in snapshot {
+        if sse_tx.send(bytes).await.is_err() {
+            info!("event stream closed while sending snapshot");
+            return;
+        }
+    }
+
+    loop {
+        let bytes = tokio::select! {
+            _ = sse_tx.closed() => {
+                info!("event stream closed by the client");
+                break;
+            }
+            result = listing_rx.recv() => match result {
+                Ok(bytes) => bytes,
+                Err(RecvError::Closed) => {
+                    info!("lobby manager has gracefully exited");
+                    break;
+                }
+                Err(RecvError::Lagged(count)) => {
+                    // Clients are expected to reconnect and receive a fresh snapshot.
+                    error!(count, "listing receiver lagged");
+                    break;
+                }
+            },
+        };
+
+        if sse_tx.send(bytes).await.is_err() {
+            info!("event stream closed by the client");
+            break;
+        }
+
+        trace!("delivered listing event to the event stream");
+    }
+}
@@ -1,171 +1,150 @@
 use crate::{
     actor::{
-        io::{event_to_websocket_msgpack_actor, websocket_msgpack_to_event_actor},
-        lobby::{wait_for_lobby_start, LobbyStart},
-        send_fn,
+        close_with_error,
+        lobby::{leave_lobby, play_in_lobby, send_players},
+        read_msgpack, ReadError,
     },
     event::{
-        game::GameStarted,
-        lobby::{JoinLobby, LobbyJoined, LobbyPlayerJoined, LobbyPlayerLeft},
-        player::{PlayerAction, PlayerResponds, PlayerRespondsWithId},
+        error::ErrorCode,
+        lobby::{GameSettings, JoinLobby, LobbyJoined, LobbyPlayerJoined},
         Event,
     },
-    router::lobby::{Lobby, LobbyManager},
+    router::lobby::{is_valid_name, is_valid_team, issue_token, Lobby, LobbyManager, LobbyPlayer},
 };
 use arcstr::ArcStr;
-use fastwebsockets::{
-    upgrade::UpgradeFut, FragmentCollectorRead, Frame, OpCode, Payload, WebSocketError, WebSocketWrite,
-};
+use fastwebsockets::{upgrade::UpgradeFut, FragmentCollectorRead, Frame, Payload, WebSocketError, WebSocketWrite};
 use slab::Slab;
 use std::sync::Mutex;
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::{io::AsyncWrite, sync::Notify};
 use tracing::{error, info, instrument, trace};
+use triomphe::Arc;
 
-#[instrument(skip(ws_writer))]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(ws_writer, token))]
 async fn send_known_players<Writer>(
     ws_writer: &mut WebSocketWrite<Writer>,
     pid: usize,
+    host: usize,
     lobby: ArcStr,
-    snapshot: Slab<ArcStr>,
+    settings: GameSettings,
+    matches: u32,
+    token: ArcStr,
+    snapshot: Slab<LobbyPlayer>,
 ) -> Result<(), WebSocketError>
 where
     Writer: AsyncWrite + Unpin,
 {
-    let bytes = rmp_serde::to_vec_named(&Event::from(LobbyJoined { pid, lobby })).unwrap();
+    let bytes =
+        rmp_serde::to_vec_named(&Event::from(LobbyJoined { pid, host, lobby, settings, matches, token })).unwrap();
     ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
-
-    for (pid, player) in snapshot {
-        let bytes = rmp_serde::to_vec_named(&Event::from(LobbyPlayerJoined { pid, player })).unwrap();
-        ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
-    }
-
-    Ok(())
-}
-
-#[instrument(skip(ws_reader, ws_writer))]
-async fn wait_for_round_trip_ping<Reader, Writer>(
-    ws_reader: &mut FragmentCollectorRead<Reader>,
-    ws_writer: &mut WebSocketWrite<Writer>,
-    count: usize,
-) -> Result<bool, WebSocketError>
-where
-    Reader: AsyncRead + Unpin,
-    Writer: AsyncWrite + Unpin,
-{
-    let bytes = rmp_serde::to_vec_named(&Event::from(GameStarted { count })).unwrap();
-    ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
-    Ok(match ws_reader.read_frame(&mut send_fn).await? {
-        Frame { fin: true, opcode: OpCode::Binary, payload, .. } => payload.is_empty(),
-        Frame { fin, opcode, payload, .. } => {
-            error!(fin, ?opcode, ?payload, "unexpected frame format");
-            false
-        }
-    })
+    send_players(ws_writer, snapshot).await
 }
 
 // TODO: Refactor so that `lid` and `pid` are kept in instrumentation spans.
 #[instrument(skip(lobbies, upgrade))]
-pub async fn guest_actor(lobbies: &Mutex<LobbyManager>, upgrade: UpgradeFut) {
-    let (ws_reader, mut ws_writer) = upgrade.await.unwrap().split(tokio::io::split);
+pub async fn guest_actor(lobbies: &Arc<Mutex<LobbyManager>>, upgrade: UpgradeFut) {
+    let ws = match upgrade.await {
+        Ok(ws) => ws,
+        Err(err) => {
+            error!(?err, "websocket upgrade failed");
+            return;
+        }
+    };
+
+    let (ws_reader, mut ws_writer) = ws.split(tokio::io::split);
     let mut ws_reader = FragmentCollectorRead::new(ws_reader);
 
-    let payload = match ws_reader.read_frame(&mut send_fn).await.unwrap() {
-        Frame { fin: true, opcode: OpCode::Binary, payload, .. } => payload,
-        Frame { fin, opcode, payload, .. } => {
-            error!(fin, ?opcode, ?payload, "unexpected frame format");
+    let JoinLobby { lid, player, team } = match read_msgpack(&mut ws_reader).await {
+        Ok(event) => event,
+        Err(ReadError::Disconnected) => return,
+        Err(ReadError::Malformed) => {
+            close_with_error(&mut ws_writer, ErrorCode::MalformedPayload).await;
             return;
         }
     };
-
-    let JoinLobby { lid, player } = rmp_serde::from_slice(&payload).unwrap();
     info!(lid, %player, "player requested to join lobby");
 
-    let (mut broadcast_rx, pid, lobby, snapshot) = {
+    let kicked = Arc::new(Notify::new());
+    let result = 'join: {
         let mut guard = lobbies.lock().unwrap();
-        let Some(Lobby { broadcast_tx, players, lobby }) = guard.lobbies.get_mut(lid) else {
+        let Some(Lobby { broadcast_tx, players, lobby, settings, host, started, matches, joins, .. }) =
+            guard.lobbies.get_mut(lid)
+        else {
             error!(lid, "lobby does not exist");
-            return;
+            break 'join Err(ErrorCode::UnknownLobby);
         };
 
+        if *started {
+            error!(%lobby, "lobby has already started");
+            break 'join Err(ErrorCode::LobbyAlreadyStarted);
+        }
+
+        if players.len() >= settings.max_players {
+            error!(%lobby, "lobby is full");
+            break 'join Err(ErrorCode::LobbyFull);
+        }
+
+        if !is_valid_name(&player) || players.iter().any(|(_, other)| other.name == player) {
+            error!(%lobby, "player name rejected");
+            break 'join Err(ErrorCode::NameRejected);
+        }
+
+        if !is_valid_team(settings, team) {
+            error!(%lobby, team, "team rejected");
+            break 'join Err(ErrorCode::InvalidTeam);
+        }
+
         trace!(%lobby, "lobby found for guest");
         let clone = players.clone();
-        let pid = players.insert(player.clone());
-
-        match broadcast_tx.send(LobbyPlayerJoined { pid, player }.into()) {
+        let token = issue_token();
+        let pid = players.insert(LobbyPlayer {
+            name: player.clone(),
+            team,
+            wins: 0,
+            joined: *joins,
+            bot: false,
+            token: Some(token.clone()),
+            kicked: Some(kicked.clone()),
+        });
+        *joins += 1;
+
+        match broadcast_tx.send(LobbyPlayerJoined { pid, player: player.clone(), team, wins: 0, bot: false }.into()) {
             Ok(count) => trace!(count, "broadcasted player joined event to receivers"),
             Err(event) => {
                 error!(?event, "lobby has already expired");
-                return;
+                players.remove(pid);
+                break 'join Err(ErrorCode::UnknownLobby);
             }
         }
 
-        (broadcast_tx.subscribe(), pid, lobby.clone(), clone)
+        let result = (
+            broadcast_tx.clone(),
+            broadcast_tx.subscribe(),
+            pid,
+            *host,
+            lobby.clone(),
+            settings.clone(),
+            *matches,
+            token,
+            clone,
+        );
+        guard.announce_player_count(lid);
+        Ok(result)
     };
 
-    'lobby: {
-        if let Err(err) = send_known_players(&mut ws_writer, pid, lobby, snapshot).await {
-            error!(?err, "websocket writer error when sending known players");
-            break 'lobby;
-        }
-
-        let LobbyStart { ready_tx, event_tx, mut broadcast_rx, count } =
-            match wait_for_lobby_start(&mut ws_writer, &mut broadcast_rx).await {
-                Ok(Some(event)) => event,
-                Ok(None) => {
-                    error!("broadcast receiver could not process new messages");
-                    break 'lobby;
-                }
-                Err(err) => {
-                    error!(?err, "websocket writer error while waiting for game start");
-                    break 'lobby;
-                }
-            };
-
-        'game: {
-            match wait_for_round_trip_ping(&mut ws_reader, &mut ws_writer, count).await {
-                Ok(true) => (),
-                Ok(false) => {
-                    error!("unexpected websocket frame format");
-                    break 'game;
-                }
-                Err(err) => {
-                    error!(?err, "websocket writer error while waiting for round trip ping");
-                    break 'game;
-                }
-            }
-
-            // Signal to the lobby that this player is ready
-            info!("player is ready");
-            drop(ready_tx);
-
-            // Play the game
-            tokio::spawn(async move { websocket_msgpack_to_event_actor(&mut ws_reader, &event_tx, pid).await });
-            tokio::spawn(async move { event_to_websocket_msgpack_actor(&mut ws_writer, &mut broadcast_rx).await });
+    let (lobby_tx, broadcast_rx, pid, host, lobby, settings, matches, token, snapshot) = match result {
+        Ok(result) => result,
+        Err(code) => {
+            close_with_error(&mut ws_writer, code).await;
             return;
         }
+    };
 
-        // Announce graceful self-elimination of player.
-        event_tx
-            .send(PlayerRespondsWithId { pid, data: PlayerResponds { next: pid, action: PlayerAction::Zip } })
-            .await
-            .expect("game must still exist");
+    if let Err(err) = send_known_players(&mut ws_writer, pid, host, lobby, settings, matches, token, snapshot).await {
+        error!(?err, "websocket writer error when sending known players");
+        leave_lobby(lobbies, &lobby_tx, lid, pid, &player, None);
         return;
     }
 
-    // Gracefully disconnect player from the lobby with notification.
-    {
-        let mut guard = lobbies.lock().unwrap();
-        let Some(Lobby { broadcast_tx, players, lobby }) = guard.lobbies.get_mut(lid) else {
-            error!("lobby has already expired");
-            return;
-        };
-
-        let player = players.remove(pid);
-        info!(%lobby, %player, "player has prematurely left the lobby");
-
-        match broadcast_tx.send(LobbyPlayerLeft { pid }.into()) {
-            Ok(count) => trace!(count, "broadcasted player leave event to receivers"),
-            Err(event) => error!(?event, "lobby has already been dissolved"),
-        }
-    }
+    play_in_lobby(lobbies, ws_reader, ws_writer, lobby_tx, broadcast_rx, lid, pid, player, kicked, None).await;
 }
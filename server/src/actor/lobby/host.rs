@@ -1,163 +1,99 @@
 use crate::{
     actor::{
-        game::handle_game,
-        io::{event_to_websocket_msgpack_actor, websocket_msgpack_to_event_actor},
-        lobby::{wait_for_lobby_start, LobbyEvent, LobbyStart},
-        send_fn,
+        close_with_error,
+        lobby::{leave_lobby, play_in_lobby},
+        read_msgpack, ReadError,
     },
     event::{
-        game::GameStarted,
-        lobby::{CreateLobby, LobbyCreated, StartGame},
-        player::PlayerRespondsWithId,
+        error::ErrorCode,
+        lobby::{CreateLobby, LobbyCreated},
         Event,
     },
-    router::lobby::{Lobby, LobbyManager},
-    zzz::ZipZapZop,
+    router::lobby::{is_valid_name, is_valid_settings, is_valid_team, issue_token, Lobby, LobbyManager, LobbyPlayer},
 };
-use core::time::Duration;
-use fastwebsockets::{upgrade::UpgradeFut, FragmentCollectorRead, Frame, OpCode, Payload, WebSocketWrite};
+use fastwebsockets::{upgrade::UpgradeFut, FragmentCollectorRead, Frame, Payload};
 use slab::Slab;
-use std::sync::Mutex;
-use tokio::{
-    io::{AsyncRead, AsyncWrite},
-    sync::{broadcast, mpsc},
-    task::JoinHandle,
-    time::timeout,
-};
-use tracing::{error, info, instrument, trace};
-
-#[instrument(skip(ws_writer, broadcast_rx))]
-async fn detach_host<Writer>(
-    mut ws_writer: WebSocketWrite<Writer>,
-    mut broadcast_rx: broadcast::Receiver<LobbyEvent>,
-    pid: usize,
-) -> mpsc::Sender<PlayerRespondsWithId>
-where
-    Writer: AsyncWrite + Send + Unpin + 'static,
-{
-    let LobbyStart { ready_tx, event_tx, mut broadcast_rx, count } =
-        wait_for_lobby_start(&mut ws_writer, &mut broadcast_rx)
-            .await
-            .expect("host websocket connection failed")
-            .expect("origin lobby was prematurely closed");
-    trace!(count, "game start command received with player count");
-
-    let bytes = rmp_serde::to_vec_named(&Event::from(GameStarted { count })).unwrap();
-    ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await.expect("host websocket writer failed");
-
-    // Signal to the lobby that this player is ready
-    info!("player is ready");
-    drop(ready_tx);
-
-    // Partial detachment of host handlers
-    tokio::spawn(async move { event_to_websocket_msgpack_actor(&mut ws_writer, &mut broadcast_rx).await });
-    event_tx // lobby must surrender ownership over the `ws_reader`
-}
-
-#[instrument(skip(ws_reader, ws_writer, broadcast_rx))]
-async fn detach_host_while_waiting_for_start_command<Reader, Writer>(
-    ws_reader: &mut FragmentCollectorRead<Reader>,
-    mut ws_writer: WebSocketWrite<Writer>,
-    broadcast_rx: broadcast::Receiver<LobbyEvent>,
-    lid: usize,
-    pid: usize,
-) -> anyhow::Result<(usize, JoinHandle<mpsc::Sender<PlayerRespondsWithId>>)>
-where
-    Reader: AsyncRead + Unpin,
-    Writer: AsyncWrite + Unpin + Send + 'static,
-{
-    let bytes = rmp_serde::to_vec_named(&Event::from(LobbyCreated { lid, pid }))?;
-    ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
-
-    // Relay lobby events to the host
-    let handle = tokio::spawn(detach_host(ws_writer, broadcast_rx, pid));
-
-    let payload = match ws_reader.read_frame(&mut send_fn).await? {
-        Frame { fin: true, opcode: OpCode::Binary, payload, .. } => payload,
-        Frame { fin, opcode, payload, .. } => {
-            error!(fin, ?opcode, ?payload, "unexpected frame format");
-            anyhow::bail!("unexpected frame format");
-        }
-    };
-
-    let StartGame { count } = rmp_serde::from_slice(&payload)?;
-    Ok((count, handle))
-}
+use std::{collections::BTreeMap, sync::Mutex};
+use tokio::sync::{broadcast, Notify};
+use tracing::{error, info, instrument};
+use triomphe::Arc;
 
 #[instrument(skip(lobbies, upgrade))]
-pub async fn host_actor(lobbies: &Mutex<LobbyManager>, upgrade: UpgradeFut, broadcast_capacity: usize) {
-    let (ws_reader, ws_writer) = upgrade.await.unwrap().split(tokio::io::split);
-    let mut ws_reader = FragmentCollectorRead::new(ws_reader);
-
-    let payload = match ws_reader.read_frame(&mut send_fn).await.unwrap() {
-        Frame { fin: true, opcode: OpCode::Binary, payload, .. } => payload,
-        Frame { fin, opcode, payload, .. } => {
-            error!(fin, ?opcode, ?payload, "unexpected frame format");
+pub async fn host_actor(lobbies: &Arc<Mutex<LobbyManager>>, upgrade: UpgradeFut) {
+    let ws = match upgrade.await {
+        Ok(ws) => ws,
+        Err(err) => {
+            error!(?err, "websocket upgrade failed");
             return;
         }
     };
 
-    let CreateLobby { player, lobby } = rmp_serde::from_slice(&payload).unwrap();
-    info!(%lobby, %player, "player requested the lobby creation");
-
-    let (broadcast_tx, broadcast_rx) = broadcast::channel(broadcast_capacity);
-    let mut players = Slab::with_capacity(1);
-
-    let pid = players.insert(player);
-    let lid = lobbies.lock().unwrap().lobbies.insert(Lobby { broadcast_tx, players, lobby });
-
-    let result = detach_host_while_waiting_for_start_command(&mut ws_reader, ws_writer, broadcast_rx, lid, pid).await;
-    let Lobby { broadcast_tx: start_tx, players, lobby } = lobbies.lock().unwrap().lobbies.remove(lid);
-    trace!(%lobby, "lobby removed by host");
+    let (ws_reader, mut ws_writer) = ws.split(tokio::io::split);
+    let mut ws_reader = FragmentCollectorRead::new(ws_reader);
 
-    let (count, handle) = match result {
-        Ok(count) => count,
-        Err(err) => {
-            error!(?err, "lobby creation failed");
+    let CreateLobby { player, lobby, team, settings } = match read_msgpack(&mut ws_reader).await {
+        Ok(event) => event,
+        Err(ReadError::Disconnected) => return,
+        Err(ReadError::Malformed) => {
+            close_with_error(&mut ws_writer, ErrorCode::MalformedPayload).await;
             return;
         }
     };
+    info!(%lobby, %player, "player requested the lobby creation");
 
-    if count != players.len() {
-        error!(count, "game was started with an incorrect number of players");
+    if !is_valid_name(&lobby) || !is_valid_name(&player) {
+        error!("lobby or player name rejected");
+        close_with_error(&mut ws_writer, ErrorCode::NameRejected).await;
         return;
     }
 
-    // TODO: Is this too much capacity for the broadcast channel?
-    let (broadcast_tx, broadcast_rx) = broadcast::channel(count * 4);
-    let (event_tx, mut event_rx) = mpsc::channel(count);
-    let (ready_tx, mut ready_rx) = mpsc::channel(1);
-
-    match start_tx.send(LobbyStart { ready_tx, event_tx, broadcast_rx, count }.into()) {
-        Ok(count) => info!(count, "dispatched game start to listeners"),
-        Err(_) => {
-            error!("no receivers for game start");
-            return;
-        }
+    if !is_valid_settings(&settings) {
+        error!(?settings, "game settings rejected");
+        close_with_error(&mut ws_writer, ErrorCode::SettingsRejected).await;
+        return;
     }
 
-    drop(start_tx);
-
-    // Fulfill the responder half of the host's I/O actor
-    match handle.await {
-        Ok(event_tx) => {
-            tokio::spawn(async move { websocket_msgpack_to_event_actor(&mut ws_reader, &event_tx, pid).await });
-            info!("detached host successfully joined");
-        }
-        Err(err) => {
-            drop(ws_reader);
-            error!(?err, "detached host failed to join");
-        }
+    if !is_valid_team(&settings, team) {
+        error!(team, "team rejected");
+        close_with_error(&mut ws_writer, ErrorCode::InvalidTeam).await;
+        return;
     }
 
-    match timeout(Duration::from_secs(4), ready_rx.recv()).await {
-        Ok(Some(_)) => unreachable!("no messages expected from game ready channel"),
-        Ok(None) => info!("all players are ready to play"),
-        Err(err) => error!(?err, "timeout elapsed - only some players responded in time"),
-    }
+    // Leave enough room for every player to join and leave in quick succession
+    let (broadcast_tx, broadcast_rx) = broadcast::channel(settings.max_players * 2);
+    let lobby_tx = broadcast_tx.clone();
+    let mut players = Slab::with_capacity(1);
 
-    drop(ready_rx);
+    let token = issue_token();
+    let kicked = Arc::new(Notify::new());
+    let pid = players.insert(LobbyPlayer {
+        name: player.clone(),
+        team,
+        wins: 0,
+        joined: 0,
+        bot: false,
+        token: Some(token.clone()),
+        kicked: Some(kicked.clone()),
+    });
+    let lid = lobbies.lock().unwrap().insert(Lobby {
+        broadcast_tx,
+        players,
+        lobby,
+        settings: settings.clone(),
+        host: pid,
+        started: false,
+        matches: 0,
+        game: None,
+        held: BTreeMap::new(),
+        joins: 1,
+    });
+
+    let bytes = rmp_serde::to_vec_named(&Event::from(LobbyCreated { lid, pid, settings, token })).unwrap();
+    if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await {
+        error!(?err, "websocket writer error while announcing lobby creation");
+        leave_lobby(lobbies, &lobby_tx, lid, pid, &player, None);
+        return;
+    }
 
-    let mut zzz = ZipZapZop::new(players, pid);
-    handle_game(&mut event_rx, &broadcast_tx, &mut zzz).await;
+    play_in_lobby(lobbies, ws_reader, ws_writer, lobby_tx, broadcast_rx, lid, pid, player, kicked, None).await;
 }
@@ -1,17 +1,76 @@
 pub mod guest;
 pub mod host;
+pub mod resume;
+pub mod spectator;
 
 use crate::{
-    event::Event,
-    router::lobby::{LobbyEvent, LobbyStart},
+    actor::{
+        bot::bot_actor,
+        close_with_error,
+        game::handle_game,
+        io::{event_to_websocket_msgpack_actor, websocket_msgpack_to_event_actor, GameExit},
+        read_binary, ReadError,
+    },
+    event::{
+        error::ErrorCode,
+        game::{GameCommand, GameStarted},
+        lobby::{
+            AddBot, GameSettings, KickPlayer, LeaveReason, LobbyHostChanged, LobbyPlayerJoined, LobbyPlayerLeft,
+            LobbyRefreshed, StartGame,
+        },
+        player::PlayerEvent,
+        Event,
+    },
+    router::{
+        game::GameProgress,
+        lobby::{
+            is_valid_bot_skill, is_valid_name, is_valid_team, HeldSeat, Lobby, LobbyEvent, LobbyManager, LobbyPlayer,
+            LobbyRejected, LobbyStart,
+        },
+    },
+    zzz::{deadline::DeadlinePolicy, sudden_death::SuddenDeath, ZipZapZop},
 };
-use fastwebsockets::{Frame, Payload, WebSocketError, WebSocketWrite};
-use tokio::{io::AsyncWrite, sync::broadcast};
-use tracing::{error, info};
+use arcstr::ArcStr;
+use core::{convert::Infallible, time::Duration};
+use fastwebsockets::{FragmentCollectorRead, Frame, Payload, WebSocketError, WebSocketWrite};
+use serde::Deserialize;
+use slab::Slab;
+use std::{sync::Mutex, time::Instant};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{broadcast, mpsc, oneshot, watch, Notify},
+    task::JoinHandle,
+    time::timeout,
+};
+use tracing::{error, info, instrument, trace, warn};
+use triomphe::Arc;
+
+/// Introduces every player currently in the lobby to the client in the order in which they joined.
+async fn send_players<Writer>(
+    ws_writer: &mut WebSocketWrite<Writer>,
+    snapshot: Slab<LobbyPlayer>,
+) -> Result<(), WebSocketError>
+where
+    Writer: AsyncWrite + Unpin,
+{
+    let mut snapshot: Vec<_> = snapshot.into_iter().collect();
+    snapshot.sort_unstable_by_key(|(_, player)| player.joined);
+    for (pid, LobbyPlayer { name, team, wins, bot, .. }) in snapshot {
+        let event = LobbyPlayerJoined { pid, player: name, team, wins, bot };
+        let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+        ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+    }
+    Ok(())
+}
 
+/// Relays lobby events to the player until the game starts. Players who fall too far behind the lobby events are
+/// introduced to everyone in the lobby once more.
 async fn wait_for_lobby_start<Writer>(
+    lobbies: &Mutex<LobbyManager>,
     ws_writer: &mut WebSocketWrite<Writer>,
     broadcast_rx: &mut broadcast::Receiver<LobbyEvent>,
+    lid: usize,
+    pid: usize,
 ) -> Result<Option<LobbyStart>, WebSocketError>
 where
     Writer: AsyncWrite + Unpin,
@@ -20,15 +79,55 @@ where
     Ok(loop {
         let event = match broadcast_rx.recv().await {
             Ok(LobbyEvent::PlayerJoined(event)) => Event::from(event),
+            Ok(LobbyEvent::PlayerLeft(event)) if event.pid == pid && event.reason == LeaveReason::Kicked => {
+                info!("player has been kicked from the lobby");
+                let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+                ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+                ws_writer.write_frame(Frame::close(1000, b"kicked by host")).await?;
+                break None;
+            }
+            Ok(LobbyEvent::PlayerLeft(event)) if event.pid == pid => {
+                info!("player has left the lobby");
+                break None;
+            }
             Ok(LobbyEvent::PlayerLeft(event)) => Event::from(event),
+            Ok(LobbyEvent::HostChanged(event)) => Event::from(event),
+            Ok(LobbyEvent::Reopened(event)) => Event::from(event),
             Ok(LobbyEvent::Start(event)) => {
                 info!("game start notification received");
                 break Some(event);
             }
-            Err(RecvError::Lagged(count)) => {
-                error!(count, "broadcast receiver lagged while waiting for lobby start");
+            Ok(LobbyEvent::Dissolved(event)) => {
+                info!(?event.reason, "lobby dissolution notification received");
+                let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+                ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+                ws_writer.write_frame(Frame::close(1000, b"lobby dissolved")).await?;
+                break None;
+            }
+            Ok(LobbyEvent::Rejected(LobbyRejected { pid: target, code })) => {
+                if target != pid {
+                    continue;
+                }
+                error!(?code, "player rejected by the lobby");
+                close_with_error(ws_writer, code).await;
                 break None;
             }
+            Err(RecvError::Lagged(count)) => {
+                warn!(count, "broadcast receiver lagged while waiting for lobby start - refreshing the lobby");
+                let refreshed =
+                    lobbies.lock().unwrap().lobbies.get(lid).map(|Lobby { players, host, matches, .. }| {
+                        (LobbyRefreshed { host: *host, matches: *matches }, players.clone())
+                    });
+                let Some((event, players)) = refreshed else {
+                    error!("lobby no longer exists after lagging behind");
+                    break None;
+                };
+                // Events that are still buffered may repeat parts of the refreshed lobby
+                let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+                ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+                send_players(ws_writer, players).await?;
+                continue;
+            }
             Err(RecvError::Closed) => {
                 error!("broadcast receiver closed while waiting for lobby start");
                 break None;
@@ -38,3 +137,653 @@ where
         ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
     })
 }
+
+/// Task that relays lobby events to the player until the game starts. See [`relay_lobby_events`].
+type RelayHandle<Writer> = JoinHandle<Option<(WebSocketWrite<Writer>, broadcast::Receiver<LobbyEvent>, LobbyStart)>>;
+
+/// Relays lobby events to the player until the game starts. The writer is surrendered back to the lobby so that it
+/// may be handed off to the game. The subscription to the lobby is kept for after the game.
+#[instrument(skip(lobbies, ws_writer, broadcast_rx))]
+async fn relay_lobby_events<Writer>(
+    lobbies: Arc<Mutex<LobbyManager>>,
+    mut ws_writer: WebSocketWrite<Writer>,
+    mut broadcast_rx: broadcast::Receiver<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+) -> Option<(WebSocketWrite<Writer>, broadcast::Receiver<LobbyEvent>, LobbyStart)>
+where
+    Writer: AsyncWrite + Unpin,
+{
+    let start = match wait_for_lobby_start(&lobbies, &mut ws_writer, &mut broadcast_rx, lid, pid).await {
+        Ok(Some(event)) => event,
+        Ok(None) => {
+            error!("broadcast receiver could not process new messages");
+            return None;
+        }
+        Err(err) => {
+            error!(?err, "websocket writer error while waiting for game start");
+            return None;
+        }
+    };
+
+    let started = GameStarted { count: start.count, seed: start.seed, rules: start.rules };
+    let bytes = rmp_serde::to_vec_named(&Event::from(started)).unwrap();
+    if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await {
+        error!(?err, "websocket writer error while announcing game start");
+        return None;
+    }
+
+    Some((ws_writer, broadcast_rx, start))
+}
+
+enum LobbyCommand {
+    /// Round trip ping (i.e., an empty message) that acknowledges the game start.
+    Ready,
+    Host(HostCommand),
+}
+
+/// Commands that only the current host of the lobby may issue.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HostCommand {
+    StartGame(StartGame),
+    KickPlayer(KickPlayer),
+    AddBot(AddBot),
+}
+
+/// Reads the next lobby command, starting with the `leftover` message from the previous game (if any).
+async fn read_lobby_command<Reader>(
+    ws_reader: &mut FragmentCollectorRead<Reader>,
+    leftover: &mut Option<Result<Payload<'static>, ReadError>>,
+) -> Result<LobbyCommand, ReadError>
+where
+    Reader: AsyncRead + Unpin,
+{
+    let payload = match leftover.take() {
+        Some(result) => result?,
+        None => read_binary(ws_reader).await?,
+    };
+    if payload.is_empty() {
+        return Ok(LobbyCommand::Ready);
+    }
+
+    rmp_serde::from_slice(&payload).map(LobbyCommand::Host).map_err(|err| {
+        error!(?err, "cannot deserialize lobby command");
+        ReadError::Malformed
+    })
+}
+
+/// Game state that has been dispatched to the players, but not yet played.
+struct PendingGame {
+    event_rx: mpsc::Receiver<PlayerEvent>,
+    broadcast_tx: broadcast::Sender<Arc<[u8]>>,
+    ready_rx: mpsc::Receiver<Infallible>,
+    progress_tx: watch::Sender<GameProgress>,
+    players: Slab<LobbyPlayer>,
+    settings: GameSettings,
+    seed: u32,
+    /// The ID of the game in the registry of running games.
+    gid: usize,
+}
+
+/// Checks whether the `player` is the current host. Player IDs of kicked players may have been reused by newcomers,
+/// so the identity of the player is checked as well.
+fn is_host(host: usize, players: &Slab<LobbyPlayer>, pid: usize, player: &ArcStr) -> bool {
+    host == pid && players.get(pid).is_some_and(|other| ArcStr::ptr_eq(&other.name, player))
+}
+
+/// Dispatches the game start to every player in the lobby (including the host).
+#[instrument(skip(lobbies, lobby_tx))]
+fn start_game(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: &ArcStr,
+    count: usize,
+) -> Result<PendingGame, ErrorCode> {
+    let mut guard = lobbies.lock().unwrap();
+    let Some(Lobby { players, settings, host, started, .. }) = guard.get_mut(lid, lobby_tx) else {
+        error!("lobby has already expired");
+        return Err(ErrorCode::UnknownLobby);
+    };
+
+    if *started {
+        error!("lobby has already started");
+        return Err(ErrorCode::LobbyAlreadyStarted);
+    }
+
+    let settings = settings.clone();
+    if !is_host(*host, players, pid, player) {
+        error!(host, "only the host may start the game");
+        return Err(ErrorCode::NotHost);
+    }
+
+    // Players may have joined or left in the meantime, which must not cost the lobby its scoreboard
+    if count != players.len() {
+        error!(count, "game was started with an incorrect number of players");
+        return Err(ErrorCode::CountMismatch);
+    }
+
+    if settings.teams > 0 {
+        let mut teams = players.iter().filter_map(|(_, player)| player.team);
+        let first = teams.next();
+        if teams.all(|team| Some(team) == first) {
+            error!("game was started with fewer than two teams");
+            return Err(ErrorCode::TeamsIncomplete);
+        }
+    }
+
+    // TODO: Is this too much capacity for the broadcast channel?
+    let (broadcast_tx, broadcast_rx) = broadcast::channel(count * 4);
+    let (start_tx, players, gid, progress_tx) = guard.start(lid, broadcast_rx.resubscribe());
+    let (event_tx, event_rx) = mpsc::channel(count);
+    let (ready_tx, ready_rx) = mpsc::channel(1);
+    let seed = rand::random();
+
+    let progress_rx = progress_tx.subscribe();
+    let start = LobbyStart { ready_tx, event_tx, broadcast_rx, progress_rx, count, seed, rules: settings.rules };
+    match start_tx.send(start.into()) {
+        Ok(count) => info!(count, "dispatched game start to listeners"),
+        Err(_) => error!("no receivers for game start"),
+    }
+
+    Ok(PendingGame { event_rx, broadcast_tx, ready_rx, progress_tx, players, settings, seed, gid })
+}
+
+/// Removes another player from the lobby on behalf of the host.
+#[instrument(skip(lobbies, lobby_tx))]
+fn kick_player(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: &ArcStr,
+    target: usize,
+) -> Result<(), ErrorCode> {
+    let mut guard = lobbies.lock().unwrap();
+    let Some(Lobby { broadcast_tx, players, host, started, .. }) = guard.get_mut(lid, lobby_tx) else {
+        error!("lobby has already expired");
+        return Err(ErrorCode::UnknownLobby);
+    };
+
+    if *started {
+        error!("lobby has already started");
+        return Err(ErrorCode::LobbyAlreadyStarted);
+    }
+
+    if !is_host(*host, players, pid, player) {
+        error!(host, "only the host may kick players");
+        return Err(ErrorCode::NotHost);
+    }
+
+    // The target may have left on their own in the meantime
+    if target == pid || !players.contains(target) {
+        warn!("kick request ignored");
+        return Ok(());
+    }
+
+    let LobbyPlayer { name, kicked, .. } = players.remove(target);
+    info!(player = %name, "player kicked from the lobby");
+    if let Some(kicked) = kicked {
+        kicked.notify_one();
+    }
+
+    match broadcast_tx.send(LobbyPlayerLeft { pid: target, reason: LeaveReason::Kicked }.into()) {
+        Ok(count) => trace!(count, "broadcasted player kick event to receivers"),
+        Err(event) => error!(?event, "lobby has already been dissolved"),
+    }
+
+    guard.announce_player_count(lid);
+    Ok(())
+}
+
+/// Adds a bot player to the lobby on behalf of the host. The bot is driven by its own actor from then on.
+#[instrument(skip(lobbies, lobby_tx, bot, skill))]
+fn add_bot(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: &ArcStr,
+    AddBot { bot, team, skill }: AddBot,
+) -> Result<(), ErrorCode> {
+    let mut guard = lobbies.lock().unwrap();
+    let Some(Lobby { broadcast_tx, players, settings, host, started, joins, .. }) = guard.get_mut(lid, lobby_tx) else {
+        error!("lobby has already expired");
+        return Err(ErrorCode::UnknownLobby);
+    };
+
+    if *started {
+        error!("lobby has already started");
+        return Err(ErrorCode::LobbyAlreadyStarted);
+    }
+
+    if !is_host(*host, players, pid, player) {
+        error!(host, "only the host may add bots");
+        return Err(ErrorCode::NotHost);
+    }
+
+    if players.len() >= settings.max_players {
+        error!("lobby is full");
+        return Err(ErrorCode::LobbyFull);
+    }
+
+    if !is_valid_name(&bot) || players.iter().any(|(_, other)| other.name == bot) {
+        error!(%bot, "bot name rejected");
+        return Err(ErrorCode::NameRejected);
+    }
+
+    if !is_valid_team(settings, team) {
+        error!(team, "team rejected");
+        return Err(ErrorCode::InvalidTeam);
+    }
+
+    if !is_valid_bot_skill(&skill) {
+        error!(?skill, "bot skill rejected");
+        return Err(ErrorCode::SettingsRejected);
+    }
+
+    let bid = players.insert(LobbyPlayer {
+        name: bot.clone(),
+        team,
+        wins: 0,
+        joined: *joins,
+        bot: true,
+        token: None,
+        kicked: None,
+    });
+    *joins += 1;
+    let roster = players.iter().map(|(pid, player)| (pid, player.clone())).collect();
+    info!(bid, %bot, ?skill, "bot added to the lobby");
+
+    match broadcast_tx.send(LobbyPlayerJoined { pid: bid, player: bot, team, wins: 0, bot: true }.into()) {
+        Ok(count) => trace!(count, "broadcasted bot joined event to receivers"),
+        Err(event) => error!(?event, "lobby has already been dissolved"),
+    }
+
+    tokio::spawn(bot_actor(broadcast_tx.subscribe(), bid, skill, settings.clone(), roster));
+    guard.announce_player_count(lid);
+    Ok(())
+}
+
+/// Removes the player from the lobby and hands over the host privileges if necessary. Returns `false` if the player
+/// can no longer leave the lobby because it has already started (or otherwise expired). Players who have already
+/// been kicked are considered to have left.
+#[instrument(skip(lobbies, lobby_tx))]
+fn leave_lobby(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: &ArcStr,
+    code: Option<ErrorCode>,
+) -> bool {
+    let mut guard = lobbies.lock().unwrap();
+    let Some(entry) = guard.get_mut(lid, lobby_tx) else {
+        error!("lobby has already expired");
+        return false;
+    };
+
+    if entry.started {
+        info!(lobby = %entry.lobby, "player can no longer leave a started lobby");
+        return false;
+    }
+
+    remove_player(&mut guard, lid, pid, player, code);
+    true
+}
+
+/// Removes the player from the lobby even if its game is still underway (e.g., upon disconnecting mid-game). The
+/// player must have already forfeited the game.
+#[instrument(skip(lobbies, lobby_tx))]
+fn abandon_lobby(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: &ArcStr,
+) {
+    let mut guard = lobbies.lock().unwrap();
+    if guard.get_mut(lid, lobby_tx).is_none() {
+        error!("lobby has already expired");
+        return;
+    }
+
+    remove_player(&mut guard, lid, pid, player, None);
+}
+
+fn remove_player(guard: &mut LobbyManager, lid: usize, pid: usize, player: &ArcStr, code: Option<ErrorCode>) {
+    let entry = &mut guard.lobbies[lid];
+
+    // Player IDs of kicked players may have been reused by newcomers
+    if !entry.players.get(pid).is_some_and(|other| ArcStr::ptr_eq(&other.name, player)) {
+        info!(lobby = %entry.lobby, %player, "player has already been kicked from the lobby");
+        return;
+    }
+
+    if let Some(code) = code {
+        entry.reject(pid, code);
+    }
+
+    entry.players.remove(pid);
+    info!(lobby = %entry.lobby, %player, "player has prematurely left the lobby");
+
+    match entry.broadcast_tx.send(LobbyPlayerLeft { pid, reason: LeaveReason::Left }.into()) {
+        Ok(count) => trace!(count, "broadcasted player leave event to receivers"),
+        Err(event) => error!(?event, "lobby has already been dissolved"),
+    }
+
+    // Bots cannot play on their own
+    if entry.players.iter().all(|(_, other)| other.bot) {
+        let Lobby { lobby, .. } = guard.remove(lid);
+        info!(%lobby, "last human player has left the lobby");
+        return;
+    }
+
+    if entry.host == pid {
+        let (next, player) =
+            entry.players.iter().filter(|(_, other)| !other.bot).min_by_key(|(_, other)| other.joined).unwrap();
+
+        info!(next, player = %player.name, "host privileges handed over to the next player");
+        entry.host = next;
+
+        match entry.broadcast_tx.send(LobbyHostChanged { pid: next }.into()) {
+            Ok(count) => trace!(count, "broadcasted host change event to receivers"),
+            Err(event) => error!(?event, "lobby has already been dissolved"),
+        }
+    }
+
+    guard.announce_player_count(lid);
+}
+
+async fn eliminate_self(event_tx: &mpsc::Sender<PlayerEvent>, pid: usize) {
+    if let Err(mpsc::error::SendError(event)) = event_tx.send(PlayerEvent::Forfeit(pid)).await {
+        error!(?event, "game has already shut down");
+    }
+}
+
+/// Plays the game on behalf of the player who started it. Since the game loop runs independently of the player's
+/// own I/O actors, the game carries on even if the host disconnects. The host may still control the game through
+/// the `command_rx` channel while connected. The lobby is reopened once the game concludes.
+#[instrument(skip(lobbies, lobby_tx, pending, command_rx))]
+async fn run_game(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    pending: PendingGame,
+    mut command_rx: mpsc::Receiver<GameCommand>,
+) {
+    let PendingGame { mut event_rx, broadcast_tx, mut ready_rx, progress_tx, players, settings, seed, gid } = pending;
+
+    match timeout(Duration::from_secs_f64(settings.ready_timeout), ready_rx.recv()).await {
+        Ok(Some(_)) => unreachable!("no messages expected from game ready channel"),
+        Ok(None) => info!("all players are ready to play"),
+        Err(err) => error!(?err, "timeout elapsed - only some players responded in time"),
+    }
+
+    drop(ready_rx);
+
+    let policy = Box::<dyn DeadlinePolicy>::from(&settings);
+    let sudden_death = SuddenDeath::new(&settings.sudden_death, Instant::now());
+    let teams = players.iter().filter_map(|(pid, player)| Some((pid, player.team?))).collect();
+    let mut seats: Vec<_> = players.iter().map(|(pid, player)| (player.joined, pid)).collect();
+    seats.sort_unstable();
+    let names = players.into_iter().map(|(pid, LobbyPlayer { name, .. })| (pid, name)).collect();
+    let mut zzz = ZipZapZop::with_random_start(names, seed)
+        .with_actions(settings.actions)
+        .with_lives(settings.lives)
+        .with_teams(teams, settings.no_teammate_targeting)
+        .with_seats(seats.into_iter().map(|(_, pid)| pid))
+        .with_rules(settings.rules)
+        .with_specials(settings.specials)
+        .with_potatoes(settings.potatoes);
+    handle_game(&mut event_rx, &mut command_rx, &broadcast_tx, &progress_tx, &mut zzz, &*policy, sudden_death).await;
+
+    let winners: Vec<_> = if zzz.winner().is_ok() { zzz.survivors().collect() } else { Vec::new() };
+    let mut guard = lobbies.lock().unwrap();
+    guard.finish(gid);
+    guard.reopen(lid, lobby_tx, &winners);
+}
+
+/// Channels through which a player takes part in a running game.
+struct GameSeat {
+    event_tx: mpsc::Sender<PlayerEvent>,
+    /// Only present for the host who started the game.
+    command_tx: Option<mpsc::Sender<GameCommand>>,
+    broadcast_rx: broadcast::Receiver<Arc<[u8]>>,
+    progress_rx: watch::Receiver<GameProgress>,
+}
+
+/// Holds the slot of the disconnected player in the running game for the grace period. Returns `true` if the player
+/// has resumed from another connection in the meantime, in which case the slot is no longer ours to give up.
+#[instrument(skip(lobbies, lobby_tx, event_tx, command_tx))]
+async fn hold_seat(
+    lobbies: &Mutex<LobbyManager>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    event_tx: &mpsc::Sender<PlayerEvent>,
+    command_tx: Option<&mpsc::Sender<GameCommand>>,
+) -> bool {
+    let (resume_tx, resume_rx) = oneshot::channel();
+    let grace_period = {
+        let mut guard = lobbies.lock().unwrap();
+        let Some(Lobby { settings, held, started: true, .. }) = guard.get_mut(lid, lobby_tx) else {
+            warn!("lobby is no longer running a game");
+            return false;
+        };
+
+        if settings.grace_period <= 0.0 {
+            info!("resumption is disabled in this lobby");
+            return false;
+        }
+
+        held.insert(pid, HeldSeat { event_tx: event_tx.clone(), command_tx: command_tx.cloned(), resume_tx });
+        settings.grace_period
+    };
+
+    info!(grace_period, "holding the slot of the disconnected player");
+    tokio::select! {
+        result = timeout(Duration::from_secs_f64(grace_period), resume_rx) => match result {
+            Ok(Ok(())) => info!("slot has been claimed"),
+            Ok(Err(_)) => warn!("slot has been dropped"),
+            Err(err) => warn!(?err, "grace period elapsed"),
+        },
+        () = event_tx.closed() => info!("game has concluded while the player was disconnected"),
+    }
+
+    // The slot may have been claimed just as the grace period elapsed
+    let mut guard = lobbies.lock().unwrap();
+    match guard.get_mut(lid, lobby_tx) {
+        Some(Lobby { held, .. }) => held.remove(&pid).is_none(),
+        None => false,
+    }
+}
+
+/// Plays the running game from the `seat` until it concludes. The host who started the game also runs the game loop
+/// on the side. Afterwards, the writer resumes relaying lobby events through the returned task. The first message
+/// that the player sent in the meantime is returned as well, since it is meant for the lobby. Returns `None` if the
+/// player is no longer in the lobby (or has resumed from another connection).
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(lobbies, ws_reader, ws_writer, lobby_tx, lobby_rx, seat, game))]
+async fn play_game<Reader, Writer>(
+    lobbies: &Arc<Mutex<LobbyManager>>,
+    ws_reader: &mut FragmentCollectorRead<Reader>,
+    mut ws_writer: WebSocketWrite<Writer>,
+    lobby_tx: &broadcast::Sender<LobbyEvent>,
+    lobby_rx: broadcast::Receiver<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: &ArcStr,
+    seat: GameSeat,
+    game: Option<(PendingGame, mpsc::Receiver<GameCommand>)>,
+) -> Option<(RelayHandle<Writer>, Result<Payload<'static>, ReadError>)>
+where
+    Reader: AsyncRead + Unpin,
+    Writer: AsyncWrite + Send + Unpin + 'static,
+{
+    let GameSeat { event_tx, command_tx, mut broadcast_rx, progress_rx } = seat;
+    let (error_tx, mut error_rx) = mpsc::channel(1);
+    let relay_lobbies = lobbies.clone();
+    let handle = tokio::spawn(async move {
+        if !event_to_websocket_msgpack_actor(&mut ws_writer, &mut broadcast_rx, &progress_rx, &mut error_rx).await {
+            return None;
+        }
+        relay_lobby_events(relay_lobbies, ws_writer, lobby_rx, lid, pid).await
+    });
+
+    // The senders must be dropped as soon as the player leaves the game
+    let play = async {
+        let (event_tx, command_tx, error_tx) = (event_tx, command_tx, error_tx);
+        match websocket_msgpack_to_event_actor(ws_reader, &event_tx, command_tx.as_ref(), &error_tx, pid).await {
+            GameExit::Concluded(result) => return Some(result),
+            GameExit::Forfeited => (),
+            GameExit::Disconnected => {
+                // Nobody is left to receive the game events
+                handle.abort();
+                if hold_seat(lobbies, lobby_tx, lid, pid, &event_tx, command_tx.as_ref()).await {
+                    info!("player has resumed from another connection");
+                    return None;
+                }
+                eliminate_self(&event_tx, pid).await;
+            }
+        }
+        abandon_lobby(lobbies, lobby_tx, lid, pid, player);
+        None
+    };
+
+    let result = match game {
+        Some((pending, command_rx)) => tokio::join!(run_game(lobbies, lobby_tx, lid, pid, pending, command_rx), play).1,
+        None => play.await,
+    };
+    result.map(|result| (handle, result))
+}
+
+/// Participates in the lobby on behalf of the player for as long as they stay connected. The current host may start
+/// a game at any point, in which case this actor also becomes responsible for running the game loop. Once the game
+/// concludes, the player is handed back to the lobby for the next game. Players who resume a running game take their
+/// `seat` in the game right away.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(lobbies, ws_reader, ws_writer, lobby_tx, lobby_rx, kicked, seat))]
+async fn play_in_lobby<Reader, Writer>(
+    lobbies: &Arc<Mutex<LobbyManager>>,
+    mut ws_reader: FragmentCollectorRead<Reader>,
+    ws_writer: WebSocketWrite<Writer>,
+    lobby_tx: broadcast::Sender<LobbyEvent>,
+    lobby_rx: broadcast::Receiver<LobbyEvent>,
+    lid: usize,
+    pid: usize,
+    player: ArcStr,
+    kicked: Arc<Notify>,
+    seat: Option<GameSeat>,
+) where
+    Reader: AsyncRead + Send + Unpin + 'static,
+    Writer: AsyncWrite + Send + Unpin + 'static,
+{
+    let mut leftover = None;
+    let mut handle = match seat {
+        Some(seat) => {
+            let played =
+                play_game(lobbies, &mut ws_reader, ws_writer, &lobby_tx, lobby_rx, lid, pid, &player, seat, None);
+            let Some((handle, result)) = played.await else {
+                return;
+            };
+            leftover = Some(result);
+            handle
+        }
+        // Relay lobby events to the player while waiting for commands
+        None => tokio::spawn(relay_lobby_events(lobbies.clone(), ws_writer, lobby_rx, lid, pid)),
+    };
+
+    loop {
+        let result = loop {
+            let command = tokio::select! {
+                // The player ID may be reused by a newcomer, so the connection must not outlive the kick
+                () = kicked.notified() => {
+                    info!("player has been kicked - no longer reading from the connection");
+                    return;
+                }
+                command = read_lobby_command(&mut ws_reader, &mut leftover) => command,
+            };
+            break match command {
+                Ok(LobbyCommand::Ready) => Ok(None),
+                Ok(LobbyCommand::Host(HostCommand::StartGame(StartGame { count }))) => {
+                    start_game(lobbies, &lobby_tx, lid, pid, &player, count).map(Some).map_err(Some)
+                }
+                Ok(LobbyCommand::Host(HostCommand::KickPlayer(KickPlayer { pid: target }))) => {
+                    match kick_player(lobbies, &lobby_tx, lid, pid, &player, target) {
+                        Ok(()) => continue,
+                        Err(code) => Err(Some(code)),
+                    }
+                }
+                Ok(LobbyCommand::Host(HostCommand::AddBot(add))) => {
+                    match add_bot(lobbies, &lobby_tx, lid, pid, &player, add) {
+                        Ok(()) => continue,
+                        Err(code) => Err(Some(code)),
+                    }
+                }
+                Err(ReadError::Disconnected) => Err(None),
+                Err(ReadError::Malformed) => Err(Some(ErrorCode::MalformedPayload)),
+            };
+        };
+
+        let pending = match result {
+            Ok(pending) => pending,
+            Err(code) => {
+                if leave_lobby(lobbies, &lobby_tx, lid, pid, &player, code) {
+                    if code.is_none() {
+                        // Nobody is left to receive the lobby events
+                        handle.abort();
+                    }
+                    return;
+                }
+
+                // Players who have been dispatched the game start must instead be eliminated from the game
+                if let Ok(Some((mut ws_writer, _, LobbyStart { event_tx, .. }))) = handle.await {
+                    if let Some(code) = code {
+                        close_with_error(&mut ws_writer, code).await;
+                    }
+                    eliminate_self(&event_tx, pid).await;
+                }
+                abandon_lobby(lobbies, &lobby_tx, lid, pid, &player);
+                return;
+            }
+        };
+
+        // Only the host who started the game may control it
+        let (command_tx, command_rx) = mpsc::channel(1);
+        let command_tx = pending.is_some().then_some(command_tx);
+
+        let start = handle.await.unwrap_or_else(|err| {
+            error!(?err, "lobby event relay failed to join");
+            None
+        });
+
+        // The game must still run to completion so that the lobby reopens and leaves the registry of running games
+        let Some((ws_writer, lobby_rx, LobbyStart { ready_tx, event_tx, broadcast_rx, progress_rx, .. })) = start
+        else {
+            error!("player could not join the game");
+            abandon_lobby(lobbies, &lobby_tx, lid, pid, &player);
+            if let Some(pending) = pending {
+                run_game(lobbies, &lobby_tx, lid, pid, pending, command_rx).await;
+            }
+            return;
+        };
+
+        // Signal to the lobby that this player is ready
+        info!("player is ready");
+        drop(ready_tx);
+
+        // Play the game, after which the writer resumes relaying lobby events
+        let seat = GameSeat { event_tx, command_tx, broadcast_rx, progress_rx };
+        let game = pending.map(|pending| (pending, command_rx));
+        let played = play_game(lobbies, &mut ws_reader, ws_writer, &lobby_tx, lobby_rx, lid, pid, &player, seat, game);
+        let Some((next, result)) = played.await else {
+            return;
+        };
+        handle = next;
+        leftover = Some(result);
+    }
+}
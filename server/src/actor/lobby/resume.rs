@@ -0,0 +1,119 @@
+use crate::{
+    actor::{
+        close_with_error,
+        lobby::{play_in_lobby, send_players, GameSeat},
+        read_msgpack, ReadError,
+    },
+    event::{
+        error::ErrorCode,
+        lobby::{LobbyResumed, ResumeLobby},
+        Event,
+    },
+    router::{
+        game::{GameProgress, RunningGame},
+        lobby::{is_valid_token, HeldSeat, Lobby, LobbyManager, LobbyPlayer},
+    },
+};
+use fastwebsockets::{upgrade::UpgradeFut, FragmentCollectorRead, Frame, Payload};
+use std::sync::Mutex;
+use tracing::{error, info, instrument, trace, warn};
+use triomphe::Arc;
+
+/// Reattaches a player who has lost their connection mid-game to their held slot. The player then carries on as if
+/// they had never left, including the lobby after the game.
+#[instrument(skip(lobbies, upgrade))]
+pub async fn resume_actor(lobbies: &Arc<Mutex<LobbyManager>>, upgrade: UpgradeFut) {
+    let ws = match upgrade.await {
+        Ok(ws) => ws,
+        Err(err) => {
+            error!(?err, "websocket upgrade failed");
+            return;
+        }
+    };
+
+    let (ws_reader, mut ws_writer) = ws.split(tokio::io::split);
+    let mut ws_reader = FragmentCollectorRead::new(ws_reader);
+
+    let ResumeLobby { lid, pid, token } = match read_msgpack(&mut ws_reader).await {
+        Ok(event) => event,
+        Err(ReadError::Disconnected) => return,
+        Err(ReadError::Malformed) => {
+            close_with_error(&mut ws_writer, ErrorCode::MalformedPayload).await;
+            return;
+        }
+    };
+    info!(lid, pid, "player requested to resume the game");
+
+    let result = 'resume: {
+        let mut guard = lobbies.lock().unwrap();
+        let LobbyManager { lobbies, games, .. } = &mut *guard;
+        let Some(Lobby { broadcast_tx, lobby, players, settings, host, matches, game, held, .. }) =
+            lobbies.get_mut(lid)
+        else {
+            error!("lobby does not exist");
+            break 'resume Err(ErrorCode::UnknownLobby);
+        };
+
+        let Some(LobbyPlayer { name, token: Some(expected), kicked: Some(kicked), .. }) = players.get(pid) else {
+            error!(%lobby, "player is not in the lobby");
+            break 'resume Err(ErrorCode::ResumeRejected);
+        };
+
+        if !is_valid_token(expected, &token) {
+            error!(%lobby, player = %name, "resume token mismatch");
+            break 'resume Err(ErrorCode::ResumeRejected);
+        }
+
+        let Some(RunningGame { progress, broadcast_rx, .. }) = game.and_then(|gid| games.get(gid)) else {
+            error!(%lobby, player = %name, "lobby is not running a game");
+            break 'resume Err(ErrorCode::ResumeRejected);
+        };
+
+        let Some(HeldSeat { event_tx, command_tx, resume_tx }) = held.remove(&pid) else {
+            error!(%lobby, player = %name, "slot of the player is not being held");
+            break 'resume Err(ErrorCode::ResumeRejected);
+        };
+
+        if resume_tx.send(()).is_err() {
+            warn!(%lobby, player = %name, "disconnected player has already given up the slot");
+        }
+
+        trace!(%lobby, player = %name, "slot claimed by resumed player");
+        let GameProgress { remaining, round, snapshot } = progress.borrow().clone();
+        let event = LobbyResumed {
+            lobby: lobby.clone(),
+            host: *host,
+            settings: settings.clone(),
+            matches: *matches,
+            remaining,
+            round,
+        };
+        let seat =
+            GameSeat { event_tx, command_tx, broadcast_rx: broadcast_rx.resubscribe(), progress_rx: progress.clone() };
+        let players = players.clone();
+        let player = (name.clone(), kicked.clone());
+        Ok((event, player, players, snapshot, broadcast_tx.clone(), broadcast_tx.subscribe(), seat))
+    };
+
+    let (event, (player, kicked), players, snapshot, lobby_tx, lobby_rx, seat) = match result {
+        Ok(result) => result,
+        Err(code) => {
+            close_with_error(&mut ws_writer, code).await;
+            return;
+        }
+    };
+
+    // A dropped connection is noticed by the reader, which holds the slot once again
+    let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+    if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await {
+        error!(?err, "websocket writer error while announcing resumption");
+    } else if let Err(err) = send_players(&mut ws_writer, players).await {
+        error!(?err, "websocket writer error when sending known players");
+    } else if let Some(bytes) = snapshot {
+        if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Borrowed(&bytes))).await {
+            error!(?err, "websocket writer error when sending game snapshot");
+        }
+    }
+
+    play_in_lobby(lobbies, ws_reader, ws_writer, lobby_tx, lobby_rx, lid, pid, player, kicked, Some(seat)).await;
+}
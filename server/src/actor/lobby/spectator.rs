@@ -0,0 +1,204 @@
+use crate::{
+    actor::{
+        close_with_error, io::event_to_websocket_msgpack_actor, lobby::send_players, read_binary, read_msgpack,
+        ReadError,
+    },
+    event::{
+        error::ErrorCode,
+        game::GameStarted,
+        lobby::{LobbyRefreshed, LobbySpectated, SpectateLobby},
+        Event,
+    },
+    router::{
+        game::GameProgress,
+        lobby::{Lobby, LobbyEvent, LobbyManager, LobbyStart},
+    },
+};
+use fastwebsockets::{upgrade::UpgradeFut, FragmentCollectorRead, Frame, Payload, WebSocketError, WebSocketWrite};
+use std::sync::Mutex;
+use tokio::{
+    io::AsyncWrite,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, error::SendError},
+        watch,
+    },
+};
+use tracing::{error, info, instrument, trace, warn};
+use triomphe::Arc;
+
+type GameReceivers = (broadcast::Receiver<Arc<[u8]>>, watch::Receiver<GameProgress>);
+
+/// Relays the lobby events (and the game events whenever a game is running) to the spectator until the lobby
+/// expires. Spectators drop their share of the game start right away so that they never hold up the ready barrier.
+/// Spectators who fall too far behind the lobby events are introduced to everyone in the lobby once more.
+#[instrument(skip(lobbies, ws_writer, lobby_rx, game_rx, error_rx))]
+async fn relay_to_spectator<Writer>(
+    lobbies: Arc<Mutex<LobbyManager>>,
+    lid: usize,
+    mut ws_writer: WebSocketWrite<Writer>,
+    mut lobby_rx: broadcast::Receiver<LobbyEvent>,
+    mut game_rx: Option<GameReceivers>,
+    mut error_rx: mpsc::Receiver<ErrorCode>,
+) -> Result<(), WebSocketError>
+where
+    Writer: AsyncWrite + Unpin,
+{
+    loop {
+        if let Some((mut broadcast_rx, progress_rx)) = game_rx.take() {
+            if !event_to_websocket_msgpack_actor(&mut ws_writer, &mut broadcast_rx, &progress_rx, &mut error_rx).await {
+                return Ok(());
+            }
+        }
+
+        let received = tokio::select! {
+            biased;
+            Some(code) = error_rx.recv() => {
+                close_with_error(&mut ws_writer, code).await;
+                return Ok(());
+            }
+            received = lobby_rx.recv() => received,
+        };
+
+        let event = match received {
+            Ok(LobbyEvent::Start(LobbyStart { broadcast_rx, progress_rx, count, seed, rules, .. })) => {
+                info!("spectating the game");
+                game_rx = Some((broadcast_rx, progress_rx));
+                Event::from(GameStarted { count, seed, rules })
+            }
+            Ok(LobbyEvent::PlayerJoined(event)) => Event::from(event),
+            Ok(LobbyEvent::PlayerLeft(event)) => Event::from(event),
+            Ok(LobbyEvent::HostChanged(event)) => Event::from(event),
+            Ok(LobbyEvent::Reopened(event)) => Event::from(event),
+            Ok(LobbyEvent::Rejected(_)) => continue,
+            Ok(LobbyEvent::Dissolved(event)) => {
+                info!(?event.reason, "lobby dissolution notification received");
+                let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+                ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+                ws_writer.write_frame(Frame::close(1000, b"lobby dissolved")).await?;
+                return Ok(());
+            }
+            Err(RecvError::Lagged(count)) => {
+                warn!(count, "broadcast receiver lagged while spectating the lobby - refreshing the lobby");
+                let refreshed =
+                    lobbies.lock().unwrap().lobbies.get(lid).map(|Lobby { players, host, matches, .. }| {
+                        (LobbyRefreshed { host: *host, matches: *matches }, players.clone())
+                    });
+                let Some((event, players)) = refreshed else {
+                    info!("spectated lobby has expired after lagging behind");
+                    ws_writer.write_frame(Frame::close(1000, b"lobby expired")).await?;
+                    return Ok(());
+                };
+                // Events that are still buffered may repeat parts of the refreshed lobby
+                let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+                ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+                send_players(&mut ws_writer, players).await?;
+                continue;
+            }
+            Err(RecvError::Closed) => {
+                info!("spectated lobby has expired");
+                ws_writer.write_frame(Frame::close(1000, b"lobby expired")).await?;
+                return Ok(());
+            }
+        };
+
+        let bytes = rmp_serde::to_vec_named(&event).unwrap();
+        ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await?;
+    }
+}
+
+#[instrument(skip(lobbies, upgrade))]
+pub async fn spectator_actor(lobbies: &Arc<Mutex<LobbyManager>>, upgrade: UpgradeFut) {
+    let ws = match upgrade.await {
+        Ok(ws) => ws,
+        Err(err) => {
+            error!(?err, "websocket upgrade failed");
+            return;
+        }
+    };
+
+    let (ws_reader, mut ws_writer) = ws.split(tokio::io::split);
+    let mut ws_reader = FragmentCollectorRead::new(ws_reader);
+
+    let SpectateLobby { lid } = match read_msgpack(&mut ws_reader).await {
+        Ok(event) => event,
+        Err(ReadError::Disconnected) => return,
+        Err(ReadError::Malformed) => {
+            close_with_error(&mut ws_writer, ErrorCode::MalformedPayload).await;
+            return;
+        }
+    };
+    info!(lid, "spectator requested to watch lobby");
+
+    let result = 'spectate: {
+        let guard = lobbies.lock().unwrap();
+        let Some(Lobby { broadcast_tx, lobby, players, settings, host, started, matches, game, .. }) =
+            guard.lobbies.get(lid)
+        else {
+            error!(lid, "lobby does not exist");
+            break 'spectate Err(ErrorCode::UnknownLobby);
+        };
+
+        trace!(%lobby, "lobby found for spectator");
+        let event = LobbySpectated {
+            lobby: lobby.clone(),
+            host: *host,
+            settings: settings.clone(),
+            matches: *matches,
+            started: *started,
+        };
+        let game = game.and_then(|gid| guard.games.get(gid));
+        let snapshot = game.and_then(|game| game.progress.borrow().snapshot.clone());
+        let game_rx = game.map(|game| (game.broadcast_rx.resubscribe(), game.progress.clone()));
+        Ok((event, players.clone(), snapshot, broadcast_tx.subscribe(), game_rx))
+    };
+
+    let (event, players, snapshot, lobby_rx, game_rx) = match result {
+        Ok(result) => result,
+        Err(code) => {
+            close_with_error(&mut ws_writer, code).await;
+            return;
+        }
+    };
+
+    let bytes = rmp_serde::to_vec_named(&Event::from(event)).unwrap();
+    if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await {
+        error!(?err, "websocket writer error while announcing spectated lobby");
+        return;
+    }
+
+    if let Err(err) = send_players(&mut ws_writer, players).await {
+        error!(?err, "websocket writer error when sending known players");
+        return;
+    }
+
+    // Spectators who join in the middle of a game catch up from the snapshot
+    if let Some(bytes) = snapshot {
+        if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Borrowed(&bytes))).await {
+            error!(?err, "websocket writer error when sending game snapshot");
+            return;
+        }
+    }
+
+    let (error_tx, error_rx) = mpsc::channel(1);
+    let lobbies = lobbies.clone();
+    let handle = tokio::spawn(async move {
+        if let Err(err) = relay_to_spectator(lobbies, lid, ws_writer, lobby_rx, game_rx, error_rx).await {
+            error!(?err, "websocket writer error while spectating");
+        }
+    });
+
+    // Spectators are not expected to say anything
+    match read_binary(&mut ws_reader).await {
+        Err(ReadError::Disconnected) => {
+            info!("spectator has left");
+            handle.abort();
+        }
+        Ok(_) | Err(ReadError::Malformed) => {
+            error!("spectators may not send messages");
+            if let Err(SendError(code)) = error_tx.send(ErrorCode::MalformedPayload).await {
+                warn!(?code, "websocket writer has already exited");
+            }
+        }
+    }
+}
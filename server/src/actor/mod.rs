@@ -1,8 +1,76 @@
+pub mod bot;
 pub mod game;
 pub mod io;
+pub mod listing;
 pub mod lobby;
 
+use crate::event::{
+    error::{Error, ErrorCode},
+    Event,
+};
+use fastwebsockets::{FragmentCollectorRead, Frame, OpCode, Payload, WebSocketWrite};
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tracing::{error, info};
+
 /// Used for split streams in [`fastwebsockets`].
 async fn send_fn<T>(_: T) -> Result<(), &'static str> {
     Err("unexpected obligated write")
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadError {
+    /// The connection has been closed. There is no one left to report to.
+    Disconnected,
+    /// The client violated the protocol. This must be reported before closing the connection.
+    Malformed,
+}
+
+async fn read_binary<Reader>(ws_reader: &mut FragmentCollectorRead<Reader>) -> Result<Payload<'static>, ReadError>
+where
+    Reader: AsyncRead + Unpin,
+{
+    match ws_reader.read_frame(&mut send_fn).await {
+        Ok(Frame { fin: true, opcode: OpCode::Binary, payload, .. }) => Ok(payload),
+        Ok(Frame { opcode: OpCode::Close, .. }) => {
+            info!("client closed the websocket connection");
+            Err(ReadError::Disconnected)
+        }
+        Ok(Frame { fin, opcode, payload, .. }) => {
+            error!(fin, ?opcode, ?payload, "unexpected frame format");
+            Err(ReadError::Malformed)
+        }
+        Err(err) => {
+            error!(?err, "websocket reader error encountered");
+            Err(ReadError::Disconnected)
+        }
+    }
+}
+
+async fn read_msgpack<T, Reader>(ws_reader: &mut FragmentCollectorRead<Reader>) -> Result<T, ReadError>
+where
+    T: DeserializeOwned,
+    Reader: AsyncRead + Unpin,
+{
+    let payload = read_binary(ws_reader).await?;
+    rmp_serde::from_slice(&payload).map_err(|err| {
+        error!(?err, "cannot deserialize payload");
+        ReadError::Malformed
+    })
+}
+
+/// Reports the error to the client before closing the WebSocket connection.
+async fn close_with_error<Writer>(ws_writer: &mut WebSocketWrite<Writer>, code: ErrorCode)
+where
+    Writer: AsyncWrite + Unpin,
+{
+    let bytes = rmp_serde::to_vec_named(&Event::from(Error::from(code))).unwrap();
+    if let Err(err) = ws_writer.write_frame(Frame::binary(Payload::Owned(bytes))).await {
+        error!(?err, ?code, "websocket writer error while reporting error");
+        return;
+    }
+
+    if let Err(err) = ws_writer.write_frame(Frame::close(code.close_code(), code.message().as_bytes())).await {
+        error!(?err, ?code, "websocket writer error while closing connection");
+    }
+}
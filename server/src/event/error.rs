@@ -0,0 +1,75 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ErrorCode {
+    /// The client sent a message that does not conform to the protocol.
+    MalformedPayload,
+    /// The requested lobby does not exist.
+    UnknownLobby,
+    /// The requested lobby is no longer accepting players.
+    LobbyAlreadyStarted,
+    /// The host started the game with a different number of players than the server knows of.
+    CountMismatch,
+    /// The proposed lobby or player name is not acceptable.
+    NameRejected,
+    /// Only the current host of the lobby may start the game.
+    NotHost,
+    /// The proposed game settings (or bot skill) exceed the limits of the server.
+    SettingsRejected,
+    /// The requested lobby has no room for more players.
+    LobbyFull,
+    /// The chosen team does not exist in the lobby (or the lobby is not played in teams).
+    InvalidTeam,
+    /// The resume token is invalid or the slot of the player is no longer held.
+    ResumeRejected,
+    /// A lobby played in teams may only start once at least two teams have players.
+    TeamsIncomplete,
+}
+
+impl ErrorCode {
+    /// Application-specific status code for the WebSocket close frame.
+    pub const fn close_code(self) -> u16 {
+        match self {
+            Self::MalformedPayload => 4000,
+            Self::UnknownLobby => 4001,
+            Self::LobbyAlreadyStarted => 4002,
+            Self::CountMismatch => 4003,
+            Self::NameRejected => 4004,
+            Self::NotHost => 4005,
+            Self::SettingsRejected => 4006,
+            Self::LobbyFull => 4007,
+            Self::InvalidTeam => 4008,
+            Self::ResumeRejected => 4009,
+            Self::TeamsIncomplete => 4010,
+        }
+    }
+
+    /// Human-readable explanation of the error.
+    pub const fn message(self) -> &'static str {
+        match self {
+            Self::MalformedPayload => "malformed payload",
+            Self::UnknownLobby => "unknown lobby",
+            Self::LobbyAlreadyStarted => "lobby already started",
+            Self::CountMismatch => "player count mismatch",
+            Self::NameRejected => "name rejected",
+            Self::NotHost => "player is not the host",
+            Self::SettingsRejected => "settings rejected",
+            Self::LobbyFull => "lobby is full",
+            Self::InvalidTeam => "invalid team",
+            Self::ResumeRejected => "resume rejected",
+            Self::TeamsIncomplete => "fewer than two teams have players",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Error {
+    pub code: ErrorCode,
+    pub message: &'static str,
+}
+
+impl From<ErrorCode> for Error {
+    fn from(code: ErrorCode) -> Self {
+        Self { code, message: code.message() }
+    }
+}
@@ -1,28 +1,148 @@
-use crate::event::player::PlayerAction;
+use crate::event::{lobby::TargetingRules, player::SpecialAction};
+use arcstr::ArcStr;
 use jiff::Timestamp;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 pub struct GameStarted {
     pub count: usize,
+    /// Seed for the random turn assignments of the game.
+    pub seed: u32,
+    /// House rules in effect for the game.
+    pub rules: TargetingRules,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct GameExpected {
+    /// The potato that the expected player holds.
+    pub token: usize,
     /// The game expects the player with this ID to respond.
     pub next: usize,
-    pub action: PlayerAction,
+    /// Position of the expected action in the vocabulary of the lobby.
+    pub index: usize,
+    /// Name of the expected action.
+    pub action: ArcStr,
     pub deadline: Timestamp,
 }
 
-#[derive(Clone, Copy, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum EliminationReason {
+    /// The player responded when it was not their turn (i.e., for a potato that they do not hold).
+    NotTheirTurn,
+    /// The player pointed to themselves or forfeited the game (e.g., by disconnecting).
+    TargetedSelf,
+    /// The player pointed to someone who is no longer in the game.
+    InvalidTarget,
+    /// The player pointed to a teammate even though the lobby forbids it.
+    TargetedTeammate,
+    /// The player pointed back to the player who just pointed to them.
+    BouncedBack,
+    /// The player pointed to someone other than their neighbours.
+    NotNeighbour,
+    /// The player played a special action that they have run out of (or cannot play right now).
+    SpecialUnavailable,
+    /// The player said the wrong action.
+    WrongAction { expected: ArcStr, actual: ArcStr },
+    /// The player did not respond before the deadline.
+    Timeout,
+    /// The player lost the tiebreak at the end of sudden death.
+    Tiebreak,
+}
+
+#[derive(Clone, Serialize)]
 pub struct GameEliminated {
     /// The ID of the eliminated player.
     pub pid: usize,
+    pub reason: EliminationReason,
+}
+
+#[derive(Clone, Serialize)]
+pub struct GameLifeLost {
+    /// The ID of the player who made the mistake.
+    pub pid: usize,
+    pub reason: EliminationReason,
+    /// Number of lives left for the player.
+    pub remaining: u8,
 }
 
+/// Commands that only the host who started the game may issue while it is running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type")]
+pub enum GameCommand {
+    /// Freezes every deadline until the game is resumed.
+    GamePause,
+    GameResume,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct GamePaused;
+
 #[derive(Clone, Copy, Serialize)]
+pub struct GameResumed;
+
+#[derive(Clone, Copy, Serialize)]
+pub struct GameSuddenDeath {
+    /// Fixed deadline (in seconds) of every turn from now on.
+    pub deadline: f64,
+    /// Successful rounds before the winner is declared by tiebreak.
+    pub rounds: u32,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct GameSpecialUsed {
+    /// The ID of the player who played the special action.
+    pub pid: usize,
+    /// The potato that was passed on.
+    pub token: usize,
+    pub special: SpecialAction,
+    /// The player who now holds the turn.
+    pub next: usize,
+    /// Number of special actions left for the player.
+    pub remaining: u8,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Standing {
+    pub pid: usize,
+    /// Final placement of the player, starting from `1` for the winner.
+    pub place: usize,
+    /// Number of successful rounds in the game before the player was eliminated.
+    pub rounds_survived: u32,
+    /// Why the player was eliminated. Absent for the winner.
+    pub reason: Option<EliminationReason>,
+    pub eliminated_at: Option<Timestamp>,
+}
+
+#[derive(Clone, Serialize)]
 pub struct GameConcluded {
-    /// The player ID of the winner.
+    /// The player ID of the winner. Absent if the game was played in teams.
+    pub pid: Option<usize>,
+    /// The winning team (if the game was played in teams).
+    pub team: Option<u8>,
+    /// Every player in the game ranked by their final placement.
+    pub standings: Vec<Standing>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RemainingPlayer {
     pub pid: usize,
+    pub player: ArcStr,
+    /// Number of lives left for the player.
+    pub lives: u8,
+    /// The team of the player (if the game is played in teams).
+    pub team: Option<u8>,
+}
+
+/// Describes the running game as a whole for players who (re)join in the middle of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GameSnapshot {
+    /// Every player who has not yet been eliminated.
+    pub players: Vec<RemainingPlayer>,
+    /// The current holder, expected action, and deadline of every potato in play.
+    pub expected: Vec<GameExpected>,
+    /// Number of successful rounds since the start of the game.
+    pub round: u32,
+    /// Whether the host has paused the game, in which case the deadlines are frozen.
+    pub paused: bool,
 }
@@ -0,0 +1,93 @@
+use arcstr::ArcStr;
+use jiff::Timestamp;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LobbyCreated {
+    pub lid: usize,
+    /// Number of players currently in the lobby (including the host).
+    pub players: usize,
+    pub lobby: ArcStr,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LobbyUpdated {
+    pub lid: usize,
+    /// Number of players currently in the lobby (including the host).
+    pub players: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LobbyDissolved {
+    pub lid: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameRunning {
+    pub gid: usize,
+    /// The lobby in which the game is played.
+    pub lid: usize,
+    pub lobby: ArcStr,
+    /// Number of players still in the game.
+    pub players: usize,
+    /// Number of successful rounds since the start of the game.
+    pub round: u32,
+    pub started_at: Timestamp,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct GameFinished {
+    pub gid: usize,
+}
+
+/// Variant names double as the `type` tag of the protocol.
+#[allow(clippy::enum_variant_names)]
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub enum ListingEvent {
+    LobbyCreated(LobbyCreated),
+    LobbyUpdated(LobbyUpdated),
+    LobbyDissolved(LobbyDissolved),
+    GameRunning(GameRunning),
+    GameFinished(GameFinished),
+}
+
+impl From<LobbyCreated> for ListingEvent {
+    fn from(value: LobbyCreated) -> Self {
+        Self::LobbyCreated(value)
+    }
+}
+
+impl From<LobbyUpdated> for ListingEvent {
+    fn from(value: LobbyUpdated) -> Self {
+        Self::LobbyUpdated(value)
+    }
+}
+
+impl From<LobbyDissolved> for ListingEvent {
+    fn from(value: LobbyDissolved) -> Self {
+        Self::LobbyDissolved(value)
+    }
+}
+
+impl From<GameRunning> for ListingEvent {
+    fn from(value: GameRunning) -> Self {
+        Self::GameRunning(value)
+    }
+}
+
+impl From<GameFinished> for ListingEvent {
+    fn from(value: GameFinished) -> Self {
+        Self::GameFinished(value)
+    }
+}
+
+impl ListingEvent {
+    /// Encodes the event as a JSON-formatted Server-Sent Event frame.
+    pub fn to_sse(&self) -> Vec<u8> {
+        let mut bytes = b"data: ".to_vec();
+        serde_json::to_writer(&mut bytes, self).unwrap();
+        bytes.extend_from_slice(b"\n\n");
+        bytes
+    }
+}
@@ -1,42 +1,304 @@
+use crate::event::player::DEFAULT_ACTIONS;
 use arcstr::ArcStr;
 use serde::{Deserialize, Serialize};
 
+/// Schedule of the deadlines throughout the game. See [`crate::zzz::deadline`] for the formulas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeadlinePolicyKind {
+    /// Decays the initial deadline exponentially by the decay rate per round.
+    Exponential,
+    /// Subtracts the deadline step from the initial deadline per round until the floor.
+    Linear,
+    /// Always gives the initial deadline.
+    Fixed,
+    /// Decays the initial deadline exponentially by the decay rate per round until the floor.
+    #[default]
+    FloorWithDecay,
+}
+
+/// Optional house rules that restrict whom a player may point to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetingRules {
+    /// Players may not point back to the player who just pointed to them (unless only two players remain).
+    pub no_bounce_back: bool,
+    /// Players sit in a circle in the order in which they joined the lobby and may only point to the player on either
+    /// side.
+    pub neighbours_only: bool,
+}
+
+/// Limits after which the game enters sudden death. Durations are in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuddenDeathSettings {
+    /// Successful rounds after which sudden death begins. Zero disables the round limit.
+    pub max_rounds: u32,
+    /// Time after the start of the game at which sudden death begins. Zero disables the time limit.
+    pub max_duration: f64,
+    /// Fixed deadline of every turn during sudden death.
+    pub deadline: f64,
+    /// Successful rounds of sudden death before the winner is declared by tiebreak.
+    pub rounds: u32,
+}
+
+impl Default for SuddenDeathSettings {
+    fn default() -> Self {
+        Self { max_rounds: 0, max_duration: 0.0, deadline: 1.0, rounds: 16 }
+    }
+}
+
+/// Rules of the game as configured by the host. Durations are in seconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Time given to the expected player in the first round after an elimination.
+    pub initial_deadline: f64,
+    /// Exponential decay of the deadline per successful round.
+    pub decay_rate: f64,
+    /// Time taken off the deadline per successful round by the linear policy.
+    pub deadline_step: f64,
+    /// The deadline never shrinks below this floor. Only the linear and floor with decay policies have a floor.
+    pub min_deadline: f64,
+    /// Time given to every player to acknowledge the game start.
+    pub ready_timeout: f64,
+    /// Maximum number of players in the lobby (including the host).
+    pub max_players: usize,
+    pub deadline_policy: DeadlinePolicyKind,
+    /// Number of mistakes each player may make before elimination. Classic rules only give one life.
+    pub lives: u8,
+    /// Number of teams that players must join. Classic rules have no teams (i.e., zero).
+    pub teams: u8,
+    /// Whether pointing to a teammate is a mistake.
+    pub no_teammate_targeting: bool,
+    /// House rules that restrict whom a player may point to.
+    pub rules: TargetingRules,
+    /// Number of potatoes that are passed around at the same time.
+    pub potatoes: u8,
+    /// Number of special actions (i.e., Skip or Reverse) that each player may play.
+    pub specials: u8,
+    /// Ordered vocabulary of actions that the players must cycle through.
+    pub actions: Vec<ArcStr>,
+    /// Limits on the length of the game. Unlimited by default.
+    pub sudden_death: SuddenDeathSettings,
+    /// Time for which the slot of a disconnected player is held in a running game. Zero disables resumption.
+    pub grace_period: f64,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            initial_deadline: 6.0,
+            decay_rate: 1.0 / 16.0,
+            deadline_step: 0.25,
+            min_deadline: 0.0,
+            ready_timeout: 4.0,
+            max_players: 16,
+            deadline_policy: DeadlinePolicyKind::default(),
+            lives: 1,
+            teams: 0,
+            no_teammate_targeting: false,
+            rules: TargetingRules::default(),
+            potatoes: 1,
+            specials: 0,
+            actions: DEFAULT_ACTIONS.to_vec(),
+            sudden_death: SuddenDeathSettings::default(),
+            grace_period: 10.0,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct CreateLobby {
     pub player: ArcStr,
     pub lobby: ArcStr,
+    /// The team of the host. Required if and only if the lobby is played in teams.
+    pub team: Option<u8>,
+    #[serde(default)]
+    pub settings: GameSettings,
 }
 
 #[derive(Serialize)]
 pub struct LobbyCreated {
     pub lid: usize,
     pub pid: usize,
+    pub settings: GameSettings,
+    /// Secret with which the player may resume a running game after a dropped connection.
+    pub token: ArcStr,
 }
 
 #[derive(Deserialize)]
 pub struct JoinLobby {
     pub lid: usize,
     pub player: ArcStr,
+    /// Required if and only if the lobby is played in teams.
+    pub team: Option<u8>,
 }
 
 #[derive(Serialize)]
 pub struct LobbyJoined {
     pub lobby: ArcStr,
     pub pid: usize,
+    /// The player ID of the current host.
+    pub host: usize,
+    pub settings: GameSettings,
+    /// Number of games that have already been played in the lobby.
+    pub matches: u32,
+    /// Secret with which the player may resume a running game after a dropped connection.
+    pub token: ArcStr,
+}
+
+#[derive(Deserialize)]
+pub struct ResumeLobby {
+    pub lid: usize,
+    pub pid: usize,
+    /// The secret issued when the player created or joined the lobby.
+    pub token: ArcStr,
+}
+
+#[derive(Serialize)]
+pub struct LobbyResumed {
+    pub lobby: ArcStr,
+    /// The player ID of the current host.
+    pub host: usize,
+    pub settings: GameSettings,
+    /// Number of games that have already been played in the lobby.
+    pub matches: u32,
+    /// Player IDs of the players who are still in the running game.
+    pub remaining: Vec<usize>,
+    /// Number of successful rounds since the start of the running game.
+    pub round: u32,
+}
+
+/// Precedes a fresh introduction of every player for clients who have fallen behind the lobby events.
+#[derive(Serialize)]
+pub struct LobbyRefreshed {
+    /// The player ID of the current host.
+    pub host: usize,
+    /// Number of games that have already been played in the lobby.
+    pub matches: u32,
+}
+
+#[derive(Deserialize)]
+pub struct SpectateLobby {
+    pub lid: usize,
+}
+
+#[derive(Serialize)]
+pub struct LobbySpectated {
+    pub lobby: ArcStr,
+    /// The player ID of the current host.
+    pub host: usize,
+    pub settings: GameSettings,
+    /// Number of games that have already been played in the lobby.
+    pub matches: u32,
+    /// Whether a game is currently running in the lobby.
+    pub started: bool,
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct LobbyPlayerJoined {
     pub pid: usize,
     pub player: ArcStr,
+    pub team: Option<u8>,
+    /// Number of games that the player has won in the lobby so far.
+    pub wins: u32,
+    /// Whether the player is a bot driven by the server.
+    pub bot: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum LeaveReason {
+    /// The player disconnected from the lobby.
+    Left,
+    /// The host removed the player from the lobby.
+    Kicked,
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct LobbyPlayerLeft {
     pub pid: usize,
+    pub reason: LeaveReason,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LobbyHostChanged {
+    /// The player ID of the new host.
+    pub pid: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum DissolveReason {
+    /// The game server is shutting down.
+    ServerShutdown,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LobbyDissolved {
+    pub reason: DissolveReason,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LobbyScore {
+    pub pid: usize,
+    pub wins: u32,
+}
+
+/// The game has concluded and the lobby is open to new players (and another game) again.
+#[derive(Clone, Debug, Serialize)]
+pub struct LobbyReopened {
+    /// Number of games that have been played in the lobby so far.
+    pub matches: u32,
+    /// Cumulative wins of every player who is still in the lobby.
+    pub scoreboard: Vec<LobbyScore>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct StartGame {
     pub count: usize,
 }
+
+#[derive(Deserialize)]
+pub struct KickPlayer {
+    pub pid: usize,
+}
+
+/// How a bot picks the next player among the valid targets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BotStrategy {
+    /// Points to any valid player at random.
+    #[default]
+    Random,
+    /// Points to the closest valid player after itself in the circle.
+    Clockwise,
+    /// Points back to the player who just pointed to it whenever the rules allow it.
+    Retaliate,
+}
+
+/// Skill of a bot player. Durations are in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BotSkill {
+    /// Average time taken to respond.
+    pub reaction_time: f64,
+    /// Maximum deviation from the average reaction time in either direction.
+    pub reaction_jitter: f64,
+    /// Probability of responding with the wrong action.
+    pub mistake_rate: f64,
+    pub strategy: BotStrategy,
+}
+
+impl Default for BotSkill {
+    fn default() -> Self {
+        Self { reaction_time: 1.0, reaction_jitter: 0.5, mistake_rate: 0.05, strategy: BotStrategy::default() }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AddBot {
+    /// The name of the bot.
+    pub bot: ArcStr,
+    /// Required if and only if the lobby is played in teams.
+    pub team: Option<u8>,
+    #[serde(default)]
+    pub skill: BotSkill,
+}
@@ -1,9 +1,18 @@
+pub mod error;
 pub mod game;
+pub mod listing;
 pub mod lobby;
 pub mod player;
 
-use game::{GameConcluded, GameEliminated, GameExpected, GameStarted};
-use lobby::{LobbyCreated, LobbyJoined, LobbyPlayerJoined, LobbyPlayerLeft};
+use error::Error;
+use game::{
+    GameConcluded, GameEliminated, GameExpected, GameLifeLost, GamePaused, GameResumed, GameSnapshot, GameSpecialUsed,
+    GameStarted, GameSuddenDeath,
+};
+use lobby::{
+    LobbyCreated, LobbyDissolved, LobbyHostChanged, LobbyJoined, LobbyPlayerJoined, LobbyPlayerLeft, LobbyRefreshed,
+    LobbyReopened, LobbyResumed, LobbySpectated,
+};
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -11,12 +20,25 @@ use serde::Serialize;
 pub enum Event {
     LobbyCreated(LobbyCreated),
     LobbyJoined(LobbyJoined),
+    LobbySpectated(LobbySpectated),
+    LobbyResumed(LobbyResumed),
+    LobbyRefreshed(LobbyRefreshed),
     LobbyPlayerJoined(LobbyPlayerJoined),
     LobbyPlayerLeft(LobbyPlayerLeft),
+    LobbyHostChanged(LobbyHostChanged),
+    LobbyDissolved(LobbyDissolved),
+    LobbyReopened(LobbyReopened),
     GameStarted(GameStarted),
     GameExpected(GameExpected),
     GameEliminated(GameEliminated),
+    GameLifeLost(GameLifeLost),
+    GameSpecialUsed(GameSpecialUsed),
+    GameSuddenDeath(GameSuddenDeath),
+    GamePaused(GamePaused),
+    GameResumed(GameResumed),
     GameConcluded(GameConcluded),
+    GameSnapshot(GameSnapshot),
+    Error(Error),
 }
 
 impl From<LobbyCreated> for Event {
@@ -31,6 +53,24 @@ impl From<LobbyJoined> for Event {
     }
 }
 
+impl From<LobbySpectated> for Event {
+    fn from(value: LobbySpectated) -> Self {
+        Self::LobbySpectated(value)
+    }
+}
+
+impl From<LobbyResumed> for Event {
+    fn from(value: LobbyResumed) -> Self {
+        Self::LobbyResumed(value)
+    }
+}
+
+impl From<LobbyRefreshed> for Event {
+    fn from(value: LobbyRefreshed) -> Self {
+        Self::LobbyRefreshed(value)
+    }
+}
+
 impl From<LobbyPlayerJoined> for Event {
     fn from(value: LobbyPlayerJoined) -> Self {
         Self::LobbyPlayerJoined(value)
@@ -43,6 +83,24 @@ impl From<LobbyPlayerLeft> for Event {
     }
 }
 
+impl From<LobbyHostChanged> for Event {
+    fn from(value: LobbyHostChanged) -> Self {
+        Self::LobbyHostChanged(value)
+    }
+}
+
+impl From<LobbyDissolved> for Event {
+    fn from(value: LobbyDissolved) -> Self {
+        Self::LobbyDissolved(value)
+    }
+}
+
+impl From<LobbyReopened> for Event {
+    fn from(value: LobbyReopened) -> Self {
+        Self::LobbyReopened(value)
+    }
+}
+
 impl From<GameStarted> for Event {
     fn from(value: GameStarted) -> Self {
         Self::GameStarted(value)
@@ -61,8 +119,50 @@ impl From<GameEliminated> for Event {
     }
 }
 
+impl From<GameLifeLost> for Event {
+    fn from(value: GameLifeLost) -> Self {
+        Self::GameLifeLost(value)
+    }
+}
+
+impl From<GameSpecialUsed> for Event {
+    fn from(value: GameSpecialUsed) -> Self {
+        Self::GameSpecialUsed(value)
+    }
+}
+
+impl From<GameSuddenDeath> for Event {
+    fn from(value: GameSuddenDeath) -> Self {
+        Self::GameSuddenDeath(value)
+    }
+}
+
+impl From<GamePaused> for Event {
+    fn from(value: GamePaused) -> Self {
+        Self::GamePaused(value)
+    }
+}
+
+impl From<GameResumed> for Event {
+    fn from(value: GameResumed) -> Self {
+        Self::GameResumed(value)
+    }
+}
+
 impl From<GameConcluded> for Event {
     fn from(value: GameConcluded) -> Self {
         Self::GameConcluded(value)
     }
 }
+
+impl From<GameSnapshot> for Event {
+    fn from(value: GameSnapshot) -> Self {
+        Self::GameSnapshot(value)
+    }
+}
+
+impl From<Error> for Event {
+    fn from(value: Error) -> Self {
+        Self::Error(value)
+    }
+}
@@ -1,27 +1,30 @@
+use arcstr::{literal, ArcStr};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub enum PlayerAction {
-    Zip = 0,
-    Zap,
-    Zop,
-}
+/// The classic action vocabulary of the game.
+pub const DEFAULT_ACTIONS: [ArcStr; 3] = [literal!("Zip"), literal!("Zap"), literal!("Zop")];
 
-impl PlayerAction {
-    pub const fn next(self) -> Self {
-        match self {
-            Self::Zip => Self::Zap,
-            Self::Zap => Self::Zop,
-            Self::Zop => Self::Zip,
-        }
-    }
+/// Limited-use power-ups that a player may play instead of the expected action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpecialAction {
+    /// Passes the expected action on to the next player without advancing the cycle.
+    Skip,
+    /// Sends the turn back to the player who just pointed to us without advancing the cycle.
+    Reverse,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PlayerResponds {
-    /// The targeted next player in the game.
+    /// The potato to pass on. Only relevant when several potatoes are in play.
+    #[serde(default)]
+    pub token: usize,
+    /// The targeted next player in the game. Ignored when reversing.
     pub next: usize,
-    pub action: PlayerAction,
+    /// The name of the action from the vocabulary of the lobby. Ignored when playing a special action.
+    pub action: ArcStr,
+    /// Plays a special action instead of the expected action.
+    #[serde(default)]
+    pub special: Option<SpecialAction>,
 }
 
 #[derive(Debug)]
@@ -29,3 +32,12 @@ pub struct PlayerRespondsWithId {
     pub pid: usize,
     pub data: PlayerResponds,
 }
+
+/// Everything that the game hears from the players.
+#[derive(Debug)]
+pub enum PlayerEvent {
+    /// The player responded to their turn (or thinks that they did).
+    Responds(PlayerRespondsWithId),
+    /// The player has left the game for good (e.g., due to a disconnection) and must be gracefully eliminated.
+    Forfeit(usize),
+}
@@ -3,6 +3,7 @@ mod event;
 mod router;
 mod zzz;
 
+use event::lobby::DissolveReason;
 use router::lobby::LobbyManager;
 use std::{net::Ipv4Addr, sync::Mutex};
 use tokio::net::TcpListener;
@@ -21,7 +22,7 @@ fn main() -> anyhow::Result<()> {
 
         let http = hyper::server::conn::http1::Builder::new();
         let manager = Arc::<Mutex<LobbyManager>>::default();
-        loop {
+        let signal = loop {
             let conn = tokio::select! {
                 biased;
                 signal = &mut signal => break signal,
@@ -40,7 +41,7 @@ fn main() -> anyhow::Result<()> {
             let service = hyper::service::service_fn(move |req| {
                 let manager = manager.clone();
                 async move {
-                    let mut res = hyper::Response::default();
+                    let mut res = hyper::Response::new(http_body_util::Either::Left(http_body_util::Empty::new()));
                     match router::route(manager, req, &mut res) {
                         Ok(()) => Ok(res),
                         Err(err) => {
@@ -53,7 +54,10 @@ fn main() -> anyhow::Result<()> {
 
             let io = hyper_util::rt::TokioIo::new(stream);
             runtime.spawn(http.serve_connection(io, service).with_upgrades().instrument(info_span!("tcp", %addr)));
-        }
+        };
+
+        manager.lock().unwrap().dissolve_all(DissolveReason::ServerShutdown);
+        signal
     });
 
     warn!("shutting down runtime in 60 seconds at most");
@@ -0,0 +1,51 @@
+use arcstr::ArcStr;
+use jiff::Timestamp;
+use slab::Slab;
+use tokio::sync::{broadcast, watch};
+use triomphe::Arc;
+
+/// Progress of a running game as published by the game loop.
+#[derive(Clone, Debug, Default)]
+pub struct GameProgress {
+    /// Player IDs of the players who are still in the game.
+    pub remaining: Vec<usize>,
+    /// Number of successful rounds since the start of the game.
+    pub round: u32,
+    /// Pre-encoded [`GameSnapshot`](crate::event::game::GameSnapshot) event. Absent until the first potato has
+    /// been announced.
+    pub snapshot: Option<Arc<[u8]>>,
+}
+
+pub struct RunningGame {
+    /// The lobby in which the game is played.
+    pub lid: usize,
+    pub lobby: ArcStr,
+    pub started_at: Timestamp,
+    pub progress: watch::Receiver<GameProgress>,
+    /// Subscription to the game events, which spectators may join at any point.
+    pub broadcast_rx: broadcast::Receiver<Arc<[u8]>>,
+}
+
+/// Registry of every game that is currently running on the server.
+#[derive(Default)]
+pub struct GameManager {
+    games: Slab<RunningGame>,
+}
+
+impl GameManager {
+    pub fn insert(&mut self, game: RunningGame) -> usize {
+        self.games.insert(game)
+    }
+
+    pub fn get(&self, gid: usize) -> Option<&RunningGame> {
+        self.games.get(gid)
+    }
+
+    pub fn remove(&mut self, gid: usize) -> Option<RunningGame> {
+        self.games.try_remove(gid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &RunningGame)> {
+        self.games.iter()
+    }
+}
@@ -1,20 +1,39 @@
+use super::game::{GameManager, GameProgress, RunningGame};
 use crate::event::{
-    lobby::{LobbyPlayerJoined, LobbyPlayerLeft},
-    player::PlayerRespondsWithId,
+    error::ErrorCode,
+    game::GameCommand,
+    listing::{GameFinished, GameRunning, ListingEvent, LobbyCreated, LobbyDissolved, LobbyUpdated},
+    lobby::{
+        BotSkill, DeadlinePolicyKind, DissolveReason, GameSettings, LobbyDissolved as LobbyDissolvedEvent,
+        LobbyHostChanged, LobbyPlayerJoined, LobbyPlayerLeft, LobbyReopened, LobbyScore, SuddenDeathSettings,
+        TargetingRules,
+    },
+    player::PlayerEvent,
 };
 use arcstr::ArcStr;
 use core::convert::Infallible;
+use hyper::body::Bytes;
+use jiff::Timestamp;
 use slab::Slab;
-use tokio::sync::{broadcast, mpsc};
+use std::collections::BTreeMap;
+use subtle::ConstantTimeEq;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Notify};
+use tracing::{info, trace, warn};
 use triomphe::Arc;
 
 #[derive(Debug)]
 pub struct LobbyStart {
     pub ready_tx: mpsc::Sender<Infallible>,
-    pub event_tx: mpsc::Sender<PlayerRespondsWithId>,
+    pub event_tx: mpsc::Sender<PlayerEvent>,
     pub broadcast_rx: broadcast::Receiver<Arc<[u8]>>,
+    /// Latest progress of the game, which helps players catch up whenever they fall behind the game events.
+    pub progress_rx: watch::Receiver<GameProgress>,
     /// Number of known players in the game.
     pub count: usize,
+    /// Seed for the random turn assignments of the game.
+    pub seed: u32,
+    /// House rules in effect for the game.
+    pub rules: TargetingRules,
 }
 
 impl Clone for LobbyStart {
@@ -22,8 +41,9 @@ impl Clone for LobbyStart {
         let ready_tx = self.ready_tx.clone();
         let event_tx = self.event_tx.clone();
         let broadcast_rx = self.broadcast_rx.resubscribe();
-        let count = self.count;
-        Self { broadcast_rx, ready_tx, event_tx, count }
+        let progress_rx = self.progress_rx.clone();
+        let Self { count, seed, rules, .. } = *self;
+        Self { broadcast_rx, progress_rx, ready_tx, event_tx, count, seed, rules }
     }
 }
 
@@ -32,6 +52,17 @@ pub enum LobbyEvent {
     Start(LobbyStart),
     PlayerJoined(LobbyPlayerJoined),
     PlayerLeft(LobbyPlayerLeft),
+    HostChanged(LobbyHostChanged),
+    Dissolved(LobbyDissolvedEvent),
+    Rejected(LobbyRejected),
+    Reopened(LobbyReopened),
+}
+
+/// Reports a protocol error to a specific player, who must then disconnect.
+#[derive(Clone, Copy, Debug)]
+pub struct LobbyRejected {
+    pub pid: usize,
+    pub code: ErrorCode,
 }
 
 impl From<LobbyStart> for LobbyEvent {
@@ -52,13 +83,338 @@ impl From<LobbyPlayerLeft> for LobbyEvent {
     }
 }
 
+impl From<LobbyHostChanged> for LobbyEvent {
+    fn from(value: LobbyHostChanged) -> Self {
+        Self::HostChanged(value)
+    }
+}
+
+impl From<LobbyDissolvedEvent> for LobbyEvent {
+    fn from(value: LobbyDissolvedEvent) -> Self {
+        Self::Dissolved(value)
+    }
+}
+
+impl From<LobbyRejected> for LobbyEvent {
+    fn from(value: LobbyRejected) -> Self {
+        Self::Rejected(value)
+    }
+}
+
+impl From<LobbyReopened> for LobbyEvent {
+    fn from(value: LobbyReopened) -> Self {
+        Self::Reopened(value)
+    }
+}
+
+/// Maximum number of characters in a lobby or player name.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Checks whether the proposed lobby or player name may be shown to other players.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && name.chars().count() <= MAX_NAME_LENGTH
+}
+
+/// Checks whether the action in a player response could belong to any vocabulary at all. Anything longer is rejected
+/// before it may be echoed to the whole lobby as a wrong action.
+pub fn is_valid_action(action: &str) -> bool {
+    action.chars().count() <= MAX_NAME_LENGTH
+}
+
+/// Maximum number of players that a single lobby may hold.
+const MAX_PLAYERS: usize = 64;
+
+/// Maximum number of seconds for any configurable deadline or timeout.
+const MAX_TIMEOUT_SECS: f64 = 60.0;
+
+/// Minimum number of seconds for the initial deadline and the ready timeout.
+const MIN_TIMEOUT_SECS: f64 = 1.0;
+
+/// Minimum number of seconds for the fixed deadline during sudden death.
+const MIN_SUDDEN_DEATH_SECS: f64 = 0.25;
+
+/// Maximum number of seconds that a game may last before sudden death.
+const MAX_GAME_SECS: f64 = 3600.0;
+
+/// Maximum number of lives that each player may start with.
+const MAX_LIVES: u8 = 9;
+
+/// Maximum number of special actions that each player may start with.
+const MAX_SPECIALS: u8 = 9;
+
+/// Maximum number of potatoes in play at the same time.
+const MAX_POTATOES: u8 = 8;
+
+/// Maximum number of teams in a single lobby.
+const MAX_TEAMS: u8 = 8;
+
+/// Maximum number of actions in the vocabulary of a lobby.
+const MAX_ACTIONS: usize = 8;
+
+/// Checks whether the proposed game settings are within the limits of the server.
+pub fn is_valid_settings(settings: &GameSettings) -> bool {
+    let GameSettings {
+        initial_deadline,
+        decay_rate,
+        deadline_step,
+        min_deadline,
+        ready_timeout,
+        max_players,
+        lives,
+        teams,
+        potatoes,
+        specials,
+        sudden_death: SuddenDeathSettings { max_duration, deadline, .. },
+        grace_period,
+        deadline_policy,
+        ..
+    } = *settings;
+    let has_floor = matches!(deadline_policy, DeadlinePolicyKind::Linear | DeadlinePolicyKind::FloorWithDecay);
+    (MIN_TIMEOUT_SECS..=MAX_TIMEOUT_SECS).contains(&initial_deadline)
+        && (0.0..=1.0).contains(&decay_rate)
+        && (0.0..=initial_deadline).contains(&deadline_step)
+        && (0.0..=initial_deadline).contains(&min_deadline)
+        && (has_floor || min_deadline == 0.0)
+        && (MIN_TIMEOUT_SECS..=MAX_TIMEOUT_SECS).contains(&ready_timeout)
+        && (2..=MAX_PLAYERS).contains(&max_players)
+        && (1..=MAX_LIVES).contains(&lives)
+        && (1..=MAX_POTATOES).contains(&potatoes)
+        && specials <= MAX_SPECIALS
+        && (0.0..=MAX_GAME_SECS).contains(&max_duration)
+        && (MIN_SUDDEN_DEATH_SECS..=MAX_TIMEOUT_SECS).contains(&deadline)
+        && (0.0..=MAX_TIMEOUT_SECS).contains(&grace_period)
+        && (teams == 0 || (2..=MAX_TEAMS).contains(&teams))
+        && (2..=MAX_ACTIONS).contains(&settings.actions.len())
+        && settings
+            .actions
+            .iter()
+            .enumerate()
+            .all(|(i, action)| is_valid_name(action) && !settings.actions[..i].contains(action))
+}
+
+/// Checks whether the bot skill is within the limits of the server.
+pub fn is_valid_bot_skill(skill: &BotSkill) -> bool {
+    let BotSkill { reaction_time, reaction_jitter, mistake_rate, .. } = *skill;
+    (0.0..=MAX_TIMEOUT_SECS).contains(&reaction_time)
+        && (0.0..=MAX_TIMEOUT_SECS).contains(&reaction_jitter)
+        && (0.0..=1.0).contains(&mistake_rate)
+}
+
+/// Checks whether the player may join the `team` under the given settings.
+pub fn is_valid_team(settings: &GameSettings, team: Option<u8>) -> bool {
+    match team {
+        Some(team) => team < settings.teams,
+        None => settings.teams == 0,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LobbyPlayer {
+    pub name: ArcStr,
+    pub team: Option<u8>,
+    /// Number of games that the player has won in this lobby.
+    pub wins: u32,
+    /// Order in which the player joined the lobby, which determines their seat in the circle.
+    pub joined: u64,
+    /// Whether the player is a bot driven by the server.
+    pub bot: bool,
+    /// Secret with which the player may resume a running game. Bots have none.
+    pub token: Option<ArcStr>,
+    /// Stops the connection of the player once they have been kicked, even if the client ignores the close frame.
+    /// Bots have none.
+    pub kicked: Option<Arc<Notify>>,
+}
+
+/// Issues a fresh secret with which a player may resume a running game after a dropped connection.
+pub fn issue_token() -> ArcStr {
+    arcstr::format!("{:032x}", rand::random::<u128>())
+}
+
+/// Checks the secret of a resuming player in constant time so that guesses learn nothing from the response time.
+pub fn is_valid_token(expected: &str, token: &str) -> bool {
+    expected.as_bytes().ct_eq(token.as_bytes()).into()
+}
+
+/// The slot of a disconnected player in a running game, which is held for the grace period in case they resume.
+pub struct HeldSeat {
+    pub event_tx: mpsc::Sender<PlayerEvent>,
+    /// Only present for the host who started the game.
+    pub command_tx: Option<mpsc::Sender<GameCommand>>,
+    /// Wakes up the disconnected player's actor once the slot has been claimed.
+    pub resume_tx: oneshot::Sender<()>,
+}
+
 pub struct Lobby {
     pub broadcast_tx: broadcast::Sender<LobbyEvent>,
     pub lobby: ArcStr,
-    pub players: Slab<ArcStr>,
+    pub players: Slab<LobbyPlayer>,
+    pub settings: GameSettings,
+    /// The player ID of the current host, who is the only one allowed to start the game.
+    pub host: usize,
+    /// Whether the host has already started the game. New players may no longer join until it concludes.
+    pub started: bool,
+    /// Number of games that have concluded in this lobby.
+    pub matches: u32,
+    /// The ID of the running game (if any) in the [`GameManager`].
+    pub game: Option<usize>,
+    /// Slots of the disconnected players in the running game by player ID.
+    pub held: BTreeMap<usize, HeldSeat>,
+    /// Number of players who have ever joined the lobby. Player IDs are reused, so this orders the players by join.
+    pub joins: u64,
+}
+
+impl Lobby {
+    /// Reports a protocol error to the player with the given `pid`.
+    pub fn reject(&self, pid: usize, code: ErrorCode) {
+        match self.broadcast_tx.send(LobbyRejected { pid, code }.into()) {
+            Ok(count) => info!(count, pid, ?code, "broadcasted player rejection to receivers"),
+            Err(_) => warn!(pid, ?code, "no receivers for player rejection"),
+        }
+    }
+
+    /// Notifies all players waiting in the lobby that it will no longer start.
+    pub fn dissolve(&self, reason: DissolveReason) {
+        match self.broadcast_tx.send(LobbyDissolvedEvent { reason }.into()) {
+            Ok(count) => info!(count, ?reason, "broadcasted lobby dissolution to receivers"),
+            Err(_) => warn!(?reason, "no receivers for lobby dissolution"),
+        }
+    }
 }
 
-#[derive(Default)]
 pub struct LobbyManager {
     pub lobbies: Slab<Lobby>,
+    /// Registry of the games that are currently running in any of the lobbies.
+    pub games: GameManager,
+    /// Pre-encoded Server-Sent Events for the live feed of open lobbies.
+    listing_tx: broadcast::Sender<Bytes>,
+}
+
+impl Default for LobbyManager {
+    fn default() -> Self {
+        let (listing_tx, _) = broadcast::channel(32);
+        Self { lobbies: Slab::new(), games: GameManager::default(), listing_tx }
+    }
+}
+
+impl LobbyManager {
+    fn announce(&self, event: impl Into<ListingEvent>) {
+        let bytes = event.into().to_sse().into();
+        match self.listing_tx.send(bytes) {
+            Ok(count) => trace!(count, "broadcasted listing event to subscribers"),
+            Err(_) => trace!("no subscribers for listing event"),
+        }
+    }
+
+    /// Advertises a new lobby to the live feed of open lobbies.
+    pub fn insert(&mut self, lobby: Lobby) -> usize {
+        let players = lobby.players.len();
+        let name = lobby.lobby.clone();
+        let lid = self.lobbies.insert(lobby);
+        self.announce(LobbyCreated { lid, players, lobby: name });
+        lid
+    }
+
+    /// Looks up an open or starting lobby. The `broadcast_tx` guards against lobby IDs that have since been reused.
+    pub fn get_mut(&mut self, lid: usize, broadcast_tx: &broadcast::Sender<LobbyEvent>) -> Option<&mut Lobby> {
+        self.lobbies.get_mut(lid).filter(|lobby| lobby.broadcast_tx.same_channel(broadcast_tx))
+    }
+
+    /// Closes the lobby to new players and removes it from the live feed of open lobbies. The game (whose events
+    /// are broadcasted to `broadcast_rx`) is registered as running in the meantime. Returns the game start channel,
+    /// a snapshot of the players, the game ID, and the channel through which the game loop reports its progress.
+    pub fn start(
+        &mut self,
+        lid: usize,
+        broadcast_rx: broadcast::Receiver<Arc<[u8]>>,
+    ) -> (broadcast::Sender<LobbyEvent>, Slab<LobbyPlayer>, usize, watch::Sender<GameProgress>) {
+        let Lobby { broadcast_tx, lobby, players, started, game, .. } = &mut self.lobbies[lid];
+        *started = true;
+
+        let remaining: Vec<_> = players.iter().map(|(pid, _)| pid).collect();
+        let count = remaining.len();
+        let (progress_tx, progress) = watch::channel(GameProgress { remaining, round: 0, snapshot: None });
+        let started_at = Timestamp::now();
+        let name = lobby.clone();
+        let gid = self.games.insert(RunningGame { lid, lobby: name.clone(), started_at, progress, broadcast_rx });
+        *game = Some(gid);
+
+        let result = (broadcast_tx.clone(), players.clone(), gid, progress_tx);
+        self.announce(LobbyDissolved { lid });
+        self.announce(GameRunning { gid, lid, lobby: name, players: count, round: 0, started_at });
+        result
+    }
+
+    /// Removes the game from the registry of running games once the game loop has exited.
+    pub fn finish(&mut self, gid: usize) {
+        if self.games.remove(gid).is_some() {
+            self.announce(GameFinished { gid });
+        }
+    }
+
+    /// Opens the lobby to new players again after a game and credits a win to each of the `winners`. The remaining
+    /// players are notified of the updated scoreboard.
+    pub fn reopen(&mut self, lid: usize, broadcast_tx: &broadcast::Sender<LobbyEvent>, winners: &[usize]) {
+        let Some(lobby) = self.get_mut(lid, broadcast_tx) else {
+            warn!(lid, "concluded lobby has already expired");
+            return;
+        };
+
+        lobby.started = false;
+        lobby.game = None;
+        lobby.matches += 1;
+        for &pid in winners {
+            if let Some(player) = lobby.players.get_mut(pid) {
+                player.wins += 1;
+            }
+        }
+
+        let scoreboard =
+            lobby.players.iter().map(|(pid, &LobbyPlayer { wins, .. })| LobbyScore { pid, wins }).collect();
+        match lobby.broadcast_tx.send(LobbyReopened { matches: lobby.matches, scoreboard }.into()) {
+            Ok(count) => info!(count, "broadcasted lobby reopening to receivers"),
+            Err(_) => warn!("no receivers for lobby reopening"),
+        }
+
+        let (players, name) = (lobby.players.len(), lobby.lobby.clone());
+        self.announce(LobbyCreated { lid, players, lobby: name });
+    }
+
+    /// Removes the lobby from the live feed of open lobbies (if it has not already been started).
+    pub fn remove(&mut self, lid: usize) -> Lobby {
+        let lobby = self.lobbies.remove(lid);
+        if !lobby.started {
+            self.announce(LobbyDissolved { lid });
+        }
+        lobby
+    }
+
+    /// Relays the current player count of an open lobby to the live feed of open lobbies.
+    pub fn announce_player_count(&self, lid: usize) {
+        if let Some(Lobby { players, started: false, .. }) = self.lobbies.get(lid) {
+            self.announce(LobbyUpdated { lid, players: players.len() });
+        }
+    }
+
+    /// Notifies the players of every open lobby that it will no longer start.
+    pub fn dissolve_all(&self, reason: DissolveReason) {
+        for (_, lobby) in self.lobbies.iter().filter(|(_, lobby)| !lobby.started) {
+            lobby.dissolve(reason);
+        }
+    }
+
+    /// Encodes every open lobby as a [`LobbyCreated`] event and every running game as a [`GameRunning`] event along
+    /// with a subscription to future changes.
+    pub fn subscribe(&self) -> (Vec<Bytes>, broadcast::Receiver<Bytes>) {
+        let lobbies =
+            self.lobbies.iter().filter(|(_, lobby)| !lobby.started).map(|(lid, Lobby { players, lobby, .. })| {
+                ListingEvent::from(LobbyCreated { lid, players: players.len(), lobby: lobby.clone() })
+            });
+        let games = self.games.iter().map(|(gid, RunningGame { lid, lobby, started_at, progress, .. })| {
+            let GameProgress { ref remaining, round, .. } = *progress.borrow();
+            let (lid, lobby, started_at) = (*lid, lobby.clone(), *started_at);
+            ListingEvent::from(GameRunning { gid, lid, lobby, players: remaining.len(), round, started_at })
+        });
+        let snapshot = lobbies.chain(games).map(|event| event.to_sse().into()).collect();
+        (snapshot, self.listing_tx.subscribe())
+    }
 }
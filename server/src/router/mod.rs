@@ -1,20 +1,30 @@
+pub mod game;
 pub mod lobby;
+pub mod sse;
 
-use crate::actor::lobby::{guest::guest_actor, host::host_actor};
+use crate::actor::{
+    listing::lobby_listing_actor,
+    lobby::{guest::guest_actor, host::host_actor, resume::resume_actor, spectator::spectator_actor},
+};
 use fastwebsockets::{upgrade, WebSocketError};
-use http_body_util::Empty;
+use http_body_util::{Either, Empty};
 use hyper::{
     body::{Bytes, Incoming},
+    header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE},
     Method, Request, Response, StatusCode,
 };
 use lobby::LobbyManager;
+use sse::EventStream;
 use std::sync::Mutex;
+use tokio::sync::mpsc;
 use triomphe::Arc;
 
+pub type ResponseBody = Either<Empty<Bytes>, EventStream>;
+
 pub fn route(
     manager: Arc<Mutex<LobbyManager>>,
     req: Request<Incoming>,
-    res: &mut Response<Empty<Bytes>>,
+    res: &mut Response<ResponseBody>,
 ) -> Result<(), WebSocketError> {
     if *req.method() != Method::GET {
         *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
@@ -22,15 +32,25 @@ pub fn route(
     }
 
     *res = match req.uri().path() {
-        "/lobbies" => todo!("event source"),
+        "/lobbies" => {
+            let (snapshot, mut listing_rx) = manager.lock().unwrap().subscribe();
+            let (sse_tx, sse_rx) = mpsc::channel(32);
+            tokio::spawn(async move { lobby_listing_actor(&sse_tx, &mut listing_rx, snapshot).await });
+
+            let mut response = Response::new(Either::Right(EventStream::new(sse_rx)));
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+            headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+            response
+        }
         "/host" => {
             if !upgrade::is_upgrade_request(&req) {
                 *res.status_mut() = StatusCode::BAD_REQUEST;
                 return Ok(());
             }
             let (response, upgrade) = upgrade::upgrade(req)?;
-            tokio::spawn(async move { host_actor(&manager, upgrade, 32).await });
-            response
+            tokio::spawn(async move { host_actor(&manager, upgrade).await });
+            response.map(Either::Left)
         }
         "/guest" => {
             if !upgrade::is_upgrade_request(&req) {
@@ -39,7 +59,25 @@ pub fn route(
             }
             let (response, upgrade) = upgrade::upgrade(req)?;
             tokio::spawn(async move { guest_actor(&manager, upgrade).await });
-            response
+            response.map(Either::Left)
+        }
+        "/resume" => {
+            if !upgrade::is_upgrade_request(&req) {
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+                return Ok(());
+            }
+            let (response, upgrade) = upgrade::upgrade(req)?;
+            tokio::spawn(async move { resume_actor(&manager, upgrade).await });
+            response.map(Either::Left)
+        }
+        "/spectate" => {
+            if !upgrade::is_upgrade_request(&req) {
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+                return Ok(());
+            }
+            let (response, upgrade) = upgrade::upgrade(req)?;
+            tokio::spawn(async move { spectator_actor(&manager, upgrade).await });
+            response.map(Either::Left)
         }
         _ => {
             *res.status_mut() = StatusCode::NOT_FOUND;
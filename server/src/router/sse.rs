@@ -0,0 +1,27 @@
+use core::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use hyper::body::{Body, Bytes, Frame};
+use tokio::sync::mpsc::Receiver;
+
+/// Response body for a Server-Sent Events stream fed by an actor.
+pub struct EventStream {
+    sse_rx: Receiver<Bytes>,
+}
+
+impl EventStream {
+    pub const fn new(sse_rx: Receiver<Bytes>) -> Self {
+        Self { sse_rx }
+    }
+}
+
+impl Body for EventStream {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.get_mut().sse_rx.poll_recv(cx).map(|bytes| bytes.map(Frame::data).map(Ok))
+    }
+}
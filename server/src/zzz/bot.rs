@@ -0,0 +1,142 @@
+use crate::event::{
+    lobby::{BotSkill, BotStrategy, GameSettings, TargetingRules},
+    player::PlayerResponds,
+};
+use arcstr::ArcStr;
+use core::time::Duration;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+
+/// The current and previous holder of a potato as observed from the game events.
+#[derive(Clone, Copy, Debug, Default)]
+struct Holder {
+    curr: Option<usize>,
+    prev: Option<usize>,
+}
+
+/// Decision making of a server-side bot player. The bot only knows as much about the game as any other player,
+/// so it keeps track of the remaining players and the potatoes through the broadcasted game events.
+pub struct Bot {
+    pid: usize,
+    skill: BotSkill,
+    /// Remaining players in the game along with their teams.
+    players: BTreeMap<usize, Option<u8>>,
+    /// Player IDs of the remaining players in the order in which they sit in the circle.
+    seats: Vec<usize>,
+    no_teammate_targeting: bool,
+    rules: TargetingRules,
+    actions: Vec<ArcStr>,
+    holders: Vec<Holder>,
+    rng: ChaCha8Rng,
+}
+
+impl Bot {
+    /// The `players` are listed along with their teams in the order in which they sit in the circle.
+    pub fn new(
+        pid: usize,
+        skill: BotSkill,
+        players: Vec<(usize, Option<u8>)>,
+        settings: &GameSettings,
+        seed: u64,
+    ) -> Self {
+        Self {
+            pid,
+            skill,
+            seats: players.iter().map(|&(pid, _)| pid).collect(),
+            players: players.into_iter().collect(),
+            no_teammate_targeting: settings.no_teammate_targeting,
+            rules: settings.rules,
+            actions: settings.actions.clone(),
+            holders: vec![Holder::default(); settings.potatoes.into()],
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Tracks who has been passed the `token`.
+    pub fn observe(&mut self, token: usize, next: usize) {
+        let Some(holder) = self.holders.get_mut(token) else {
+            return;
+        };
+        // Potatoes are announced again (e.g., after a pause) without changing hands
+        if holder.curr != Some(next) {
+            *holder = Holder { curr: Some(next), prev: holder.curr };
+        }
+    }
+
+    /// Forgets about the eliminated player, whose potatoes are handed to other players at random.
+    pub fn eliminate(&mut self, pid: usize) {
+        self.players.remove(&pid);
+        self.seats.retain(|&other| other != pid);
+        for holder in &mut self.holders {
+            if holder.curr == Some(pid) {
+                *holder = Holder::default();
+            } else if holder.prev == Some(pid) {
+                holder.prev = None;
+            }
+        }
+    }
+
+    /// The player keeps their potatoes after losing a life, but nobody has passed them on yet.
+    pub fn lose_life(&mut self, pid: usize) {
+        for holder in self.holders.iter_mut().filter(|holder| holder.curr == Some(pid)) {
+            holder.prev = None;
+        }
+    }
+
+    /// Samples the time taken to respond from the reaction time distribution.
+    pub fn reaction_time(&mut self) -> Duration {
+        let BotSkill { reaction_time, reaction_jitter, .. } = self.skill;
+        let jitter = if reaction_jitter > 0.0 { self.rng.gen_range(-reaction_jitter..=reaction_jitter) } else { 0.0 };
+        Duration::from_secs_f64((reaction_time + jitter).max(0.0))
+    }
+
+    /// Players that the bot may point to without breaking any of the rules, going around the circle starting with
+    /// the player after the bot.
+    fn targets(&self, token: usize) -> Vec<usize> {
+        let team = self.players.get(&self.pid).copied().flatten();
+        let prev = self.holders.get(token).and_then(|holder| holder.prev);
+        let seats = &self.seats;
+        let Some(seat) = seats.iter().position(|&pid| pid == self.pid) else {
+            return Vec::new();
+        };
+        let count = seats.len();
+        let neighbours = [seats[(seat + 1) % count], seats[(seat + count - 1) % count]];
+
+        seats[seat..]
+            .iter()
+            .chain(&seats[..seat])
+            .copied()
+            .filter(|&pid| pid != self.pid)
+            .filter(|pid| !self.no_teammate_targeting || team.is_none() || self.players.get(pid) != Some(&team))
+            .filter(|&pid| !self.rules.no_bounce_back || count <= 2 || prev != Some(pid))
+            .filter(|pid| !self.rules.neighbours_only || neighbours.contains(pid))
+            .collect()
+    }
+
+    /// Passes on the `token` according to the strategy of the bot. The `action` is the expected one, which the bot
+    /// may still get wrong on purpose.
+    pub fn respond(&mut self, token: usize, action: ArcStr) -> PlayerResponds {
+        let mut targets = self.targets(token);
+        if targets.is_empty() {
+            // Every move is a mistake at this point
+            targets = self.seats.iter().copied().filter(|&pid| pid != self.pid).collect();
+        }
+
+        let prev = self.holders.get(token).and_then(|holder| holder.prev);
+        let next = match self.skill.strategy {
+            BotStrategy::Clockwise => targets.first().copied(),
+            BotStrategy::Retaliate if prev.is_some_and(|prev| targets.contains(&prev)) => prev,
+            BotStrategy::Random | BotStrategy::Retaliate => targets.choose(&mut self.rng).copied(),
+        };
+
+        let action = if self.rng.gen_bool(self.skill.mistake_rate) {
+            let wrong: Vec<_> = self.actions.iter().filter(|&other| *other != action).collect();
+            wrong.choose(&mut self.rng).map_or(action, |&wrong| wrong.clone())
+        } else {
+            action
+        };
+
+        PlayerResponds { token, next: next.unwrap_or(self.pid), action, special: None }
+    }
+}
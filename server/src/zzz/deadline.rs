@@ -0,0 +1,81 @@
+use crate::{
+    event::lobby::{DeadlinePolicyKind, GameSettings},
+    zzz::record::Elimination,
+};
+use core::time::Duration;
+
+/// Schedules the time given to the expected player in each round. The built-in policies only pace the game by the
+/// round, which restarts after every elimination anyway. The number of `remaining` players and the elimination
+/// `history` are there for policies that pace the game by how far it has progressed.
+pub trait DeadlinePolicy: Send + Sync {
+    /// The deadline for the current `round`, which counts the successful turns since the last elimination.
+    fn deadline(&self, round: u32, remaining: usize, history: &[Elimination]) -> Duration;
+}
+
+/// Shrinks the deadline by a constant factor every round.
+#[derive(Clone, Copy, Debug)]
+pub struct Exponential {
+    pub initial: Duration,
+    pub decay_rate: f64,
+}
+
+impl DeadlinePolicy for Exponential {
+    fn deadline(&self, round: u32, _: usize, _: &[Elimination]) -> Duration {
+        self.initial.mul_f64((-f64::from(round) * self.decay_rate).exp())
+    }
+}
+
+/// Shrinks the deadline by a constant amount every round until it reaches the floor.
+#[derive(Clone, Copy, Debug)]
+pub struct Linear {
+    pub initial: Duration,
+    pub step: Duration,
+    pub floor: Duration,
+}
+
+impl DeadlinePolicy for Linear {
+    fn deadline(&self, round: u32, _: usize, _: &[Elimination]) -> Duration {
+        self.initial.saturating_sub(self.step.saturating_mul(round)).max(self.floor)
+    }
+}
+
+/// Gives the same amount of time every round.
+#[derive(Clone, Copy, Debug)]
+pub struct Fixed(pub Duration);
+
+impl DeadlinePolicy for Fixed {
+    fn deadline(&self, _: u32, _: usize, _: &[Elimination]) -> Duration {
+        self.0
+    }
+}
+
+/// Shrinks the deadline exponentially, but never below the floor.
+#[derive(Clone, Copy, Debug)]
+pub struct FloorWithDecay {
+    pub decay: Exponential,
+    pub floor: Duration,
+}
+
+impl DeadlinePolicy for FloorWithDecay {
+    fn deadline(&self, round: u32, remaining: usize, history: &[Elimination]) -> Duration {
+        self.decay.deadline(round, remaining, history).max(self.floor)
+    }
+}
+
+impl From<&GameSettings> for Box<dyn DeadlinePolicy> {
+    fn from(settings: &GameSettings) -> Self {
+        let GameSettings { initial_deadline, decay_rate, deadline_step, min_deadline, deadline_policy, .. } = *settings;
+        let initial = Duration::from_secs_f64(initial_deadline);
+        let floor = Duration::from_secs_f64(min_deadline);
+        match deadline_policy {
+            DeadlinePolicyKind::Exponential => Box::new(Exponential { initial, decay_rate }),
+            DeadlinePolicyKind::Linear => {
+                Box::new(Linear { initial, step: Duration::from_secs_f64(deadline_step), floor })
+            }
+            DeadlinePolicyKind::Fixed => Box::new(Fixed(initial)),
+            DeadlinePolicyKind::FloorWithDecay => {
+                Box::new(FloorWithDecay { decay: Exponential { initial, decay_rate }, floor })
+            }
+        }
+    }
+}
@@ -1,15 +1,32 @@
+pub mod bot;
+pub mod deadline;
+pub mod record;
+pub mod sudden_death;
+
 #[cfg(test)]
 mod tests;
 
 use crate::event::{
-    game::GameExpected,
-    player::{PlayerAction, PlayerResponds, PlayerRespondsWithId},
+    game::{EliminationReason, GameExpected, GameSnapshot, RemainingPlayer},
+    lobby::TargetingRules,
+    player::{PlayerResponds, PlayerRespondsWithId, SpecialAction, DEFAULT_ACTIONS},
 };
+use arcstr::ArcStr;
 use core::fmt::Debug;
 use jiff::Timestamp;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use slab::Slab;
 use tracing::{info, instrument, warn};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Winner {
+    /// The last player standing.
+    Player(usize),
+    /// Every remaining player belongs to this team.
+    Team(u8),
+}
+
 pub enum GameWinnerError {
     /// Occurs when the lobby has no players to begin with.
     EmptyLobby,
@@ -17,32 +34,226 @@ pub enum GameWinnerError {
     MorePlayers,
 }
 
+/// A "potato" that is passed around the players. Every potato has its own holder and action cycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Token {
+    curr: usize,
+    /// The player who passed the potato to the current holder (if any).
+    prev: Option<usize>,
+    /// Index of the next expected action in the vocabulary.
+    action: usize,
+}
+
+impl Token {
+    const fn new(curr: usize) -> Self {
+        Self { curr, prev: None, action: 0 }
+    }
+}
+
 #[derive(Debug)]
 pub struct ZipZapZop<Player> {
     players: Slab<Player>,
-    curr: usize,
-    action: PlayerAction,
+    /// Remaining lives of each player in the game. A player is eliminated upon losing their last life.
+    lives: Slab<u8>,
+    /// Remaining special actions of each player in the game.
+    specials: Slab<u8>,
+    /// Team of each player in the game. Empty if the game is not played in teams.
+    teams: Slab<u8>,
+    /// Whether pointing to a teammate is a mistake.
+    no_teammate_targeting: bool,
+    /// Player IDs of the remaining players in the order in which they sit in the circle.
+    seats: Vec<usize>,
+    rules: TargetingRules,
+    /// Potatoes in play, indexed by their token ID.
+    tokens: Vec<Token>,
+    /// Ordered vocabulary of actions that the players must cycle through.
+    actions: Vec<ArcStr>,
+    /// Source of randomness for turn assignments. Seeded so that games can be reproduced exactly.
+    rng: ChaCha8Rng,
 }
 
 impl<Player> ZipZapZop<Player> {
-    pub const fn new(players: Slab<Player>, curr: usize) -> Self {
-        Self { players, curr, action: PlayerAction::Zip }
+    pub fn new(players: Slab<Player>, curr: usize, seed: u32) -> Self {
+        let lives = players.iter().map(|(pid, _)| (pid, 1)).collect();
+        let specials = players.iter().map(|(pid, _)| (pid, 0)).collect();
+        let seats = players.iter().map(|(pid, _)| pid).collect();
+        let rng = ChaCha8Rng::seed_from_u64(seed.into());
+        Self {
+            players,
+            lives,
+            specials,
+            teams: Slab::new(),
+            no_teammate_targeting: false,
+            seats,
+            rules: TargetingRules::default(),
+            tokens: vec![Token::new(curr)],
+            actions: DEFAULT_ACTIONS.to_vec(),
+            rng,
+        }
     }
 
-    /// The action for the next expected message.
-    pub const fn expects(&self, deadline: Timestamp) -> GameExpected {
-        let Self { curr, action, .. } = *self;
-        GameExpected { next: curr, action, deadline }
+    /// Gives every player the same number of `lives` (at least one).
+    pub fn with_lives(mut self, lives: u8) -> Self {
+        let lives = lives.max(1);
+        for (_, count) in &mut self.lives {
+            *count = lives;
+        }
+        self
     }
 
-    pub fn winner(&self) -> Result<(usize, &Player), GameWinnerError> {
-        let mut iter = self.players.iter();
-        let first = iter.next().ok_or(GameWinnerError::EmptyLobby)?;
-        if iter.next().is_none() {
-            Ok(first)
-        } else {
-            Err(GameWinnerError::MorePlayers)
+    /// Gives every player the same number of special actions.
+    pub fn with_specials(mut self, specials: u8) -> Self {
+        for (_, count) in &mut self.specials {
+            *count = specials;
+        }
+        self
+    }
+
+    /// Replaces the classic vocabulary with custom `actions` (unless empty).
+    pub fn with_actions(mut self, actions: Vec<ArcStr>) -> Self {
+        if !actions.is_empty() {
+            self.actions = actions;
         }
+        self
+    }
+
+    /// Plays the game in teams, where the last team standing wins.
+    pub fn with_teams(mut self, teams: Slab<u8>, no_teammate_targeting: bool) -> Self {
+        self.teams = teams;
+        self.no_teammate_targeting = no_teammate_targeting;
+        self
+    }
+
+    /// Seats the players around the circle in the given order of player IDs (e.g., by join) rather than by player ID.
+    pub fn with_seats(mut self, seats: impl IntoIterator<Item = usize>) -> Self {
+        self.seats = seats.into_iter().filter(|&pid| self.players.contains(pid)).collect();
+        self
+    }
+
+    /// Enforces the house `rules` on every turn.
+    pub const fn with_rules(mut self, rules: TargetingRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Starts the game with a randomly chosen player.
+    pub fn with_random_start(players: Slab<Player>, seed: u32) -> Self {
+        let mut zzz = Self::new(players, 0, seed);
+        if let Some(curr) = zzz.random_player() {
+            zzz.tokens[0].curr = curr;
+        }
+        zzz
+    }
+
+    /// Puts `count` potatoes (at least one) into play at once. The extra potatoes are handed to players who are not
+    /// yet holding one (if possible).
+    pub fn with_potatoes(mut self, count: u8) -> Self {
+        for _ in self.tokens.len()..count.into() {
+            let idle: Vec<_> =
+                self.survivors().filter(|&pid| self.tokens.iter().all(|token| token.curr != pid)).collect();
+            let curr =
+                if idle.is_empty() { self.random_player() } else { Some(idle[self.rng.gen_range(0..idle.len())]) };
+            let Some(curr) = curr else {
+                break;
+            };
+            self.tokens.push(Token::new(curr));
+        }
+        self
+    }
+
+    /// Picks any of the remaining players with equal probability.
+    fn random_player(&mut self) -> Option<usize> {
+        let count = self.players.len();
+        if count == 0 {
+            return None;
+        }
+        let nth = self.rng.gen_range(0..count);
+        self.players.iter().nth(nth).map(|(pid, _)| pid)
+    }
+
+    /// The action for the next expected message of the `token`.
+    pub fn expects(&self, token: usize, deadline: Timestamp) -> GameExpected {
+        let Token { curr, action, .. } = self.tokens[token];
+        GameExpected { token, next: curr, index: action, action: self.actions[action].clone(), deadline }
+    }
+
+    /// Describes the game as a whole. Potatoes without a `deadline` have yet to be announced and are left out.
+    pub fn snapshot(&self, deadlines: &[Option<Timestamp>], round: u32, paused: bool) -> GameSnapshot
+    where
+        Player: Clone + Into<ArcStr>,
+    {
+        let players = self
+            .players
+            .iter()
+            .map(|(pid, player)| RemainingPlayer {
+                pid,
+                player: player.clone().into(),
+                lives: self.lives[pid],
+                team: self.team(pid),
+            })
+            .collect();
+        let expected = deadlines
+            .iter()
+            .enumerate()
+            .filter_map(|(token, &deadline)| Some(self.expects(token, deadline?)))
+            .collect();
+        GameSnapshot { players, expected, round, paused }
+    }
+
+    /// The number of potatoes in play.
+    pub fn tokens(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// The player currently holding the `token`.
+    pub fn holder(&self, token: usize) -> usize {
+        self.tokens[token].curr
+    }
+
+    /// Whether the turn of the `token` that began with the `holder` must start over after `pid` has been penalized.
+    /// This is the case if the holder was the one penalized or if the potato has since been handed to someone else.
+    pub fn is_restarted(&self, token: usize, holder: usize, pid: usize) -> bool {
+        holder == pid || self.tokens[token].curr != holder
+    }
+
+    /// The number of players who have not yet been eliminated.
+    pub fn remaining(&self) -> usize {
+        self.players.len()
+    }
+
+    /// The IDs of the players who have not yet been eliminated.
+    pub fn survivors(&self) -> impl Iterator<Item = usize> + '_ {
+        self.players.iter().map(|(pid, _)| pid)
+    }
+
+    /// The team of the player (if the game is played in teams).
+    pub fn team(&self, pid: usize) -> Option<u8> {
+        self.teams.get(pid).copied()
+    }
+
+    fn are_teammates(&self, first: usize, second: usize) -> bool {
+        self.teams.get(first).is_some_and(|team| self.teams.get(second) == Some(team))
+    }
+
+    /// Checks whether both players sit next to each other in the circle of remaining players.
+    fn are_neighbours(&self, first: usize, second: usize) -> bool {
+        let seats = &self.seats;
+        let Some(seat) = seats.iter().position(|&pid| pid == first) else {
+            return false;
+        };
+        let count = seats.len();
+        seats[(seat + 1) % count] == second || seats[(seat + count - 1) % count] == second
+    }
+
+    pub fn winner(&self) -> Result<Winner, GameWinnerError> {
+        let mut iter = self.survivors();
+        let first = iter.next().ok_or(GameWinnerError::EmptyLobby)?;
+        let winner = match self.teams.get(first).copied() {
+            Some(team) if iter.all(|pid| self.teams.get(pid) == Some(&team)) => Winner::Team(team),
+            None if iter.next().is_none() => Winner::Player(first),
+            _ => return Err(GameWinnerError::MorePlayers),
+        };
+        Ok(winner)
     }
 }
 
@@ -53,7 +264,11 @@ pub enum TickResult<Player> {
     /// The player issued a legal command.
     Proceed,
     /// The player was eliminated as a result of this game tick.
-    Eliminated(Player),
+    Eliminated(Player, EliminationReason),
+    /// The player lost one of their lives, but remains in the game.
+    LifeLost { reason: EliminationReason, remaining: u8 },
+    /// The player legally played a special action, which passed the turn to `next`.
+    Special { special: SpecialAction, next: usize, remaining: u8 },
 }
 
 impl<Player: Debug> ZipZapZop<Player> {
@@ -61,52 +276,155 @@ impl<Player: Debug> ZipZapZop<Player> {
     ///
     /// * If the `player` does not exist in the lobby, this is a no-op.
     /// * If the `player` is not the expected sender, they will be eliminated.
-    /// * If the `player` is equal to `next`, `player` will be gracefully eliminated.
+    /// * If the `player` is equal to `next`, `player` made a mistake by targeting themselves (unless reversing).
+    /// * A reversal sends the turn back to the previous player regardless of the targeting rules.
     #[instrument]
     pub fn tick(
         &mut self,
-        PlayerRespondsWithId { pid, data: PlayerResponds { next, action } }: PlayerRespondsWithId,
+        PlayerRespondsWithId { pid, data: PlayerResponds { token, next, action, special } }: PlayerRespondsWithId,
     ) -> TickResult<Player> {
         if !self.players.contains(pid) {
             warn!("player does not exist in the game");
             return TickResult::NoOp;
         }
 
-        let must_reassign = 'eliminate: {
-            if pid != self.curr {
-                warn!(curr = self.curr, "player eliminated because it is not their turn");
-                break 'eliminate false;
+        let reason = 'eliminate: {
+            let reversed = special == Some(SpecialAction::Reverse);
+            if pid == next && !reversed {
+                warn!("player eliminated due to targeting themselves");
+                break 'eliminate EliminationReason::TargetedSelf;
             }
 
-            if pid == next {
-                warn!("player eliminated due to graceful elimination");
-                break 'eliminate true;
+            let Some(&Token { curr, prev, action: expected }) = self.tokens.get(token) else {
+                warn!(token, "player eliminated due to passing a non-existent potato");
+                break 'eliminate EliminationReason::NotTheirTurn;
+            };
+
+            if pid != curr {
+                warn!(curr, "player eliminated because it is not their turn");
+                break 'eliminate EliminationReason::NotTheirTurn;
+            }
+
+            if special.is_some() && self.specials[pid] == 0 {
+                warn!(?special, "player eliminated due to running out of special actions");
+                break 'eliminate EliminationReason::SpecialUnavailable;
             }
 
+            let next = match (reversed, prev) {
+                (false, _) => next,
+                (true, Some(prev)) => prev,
+                (true, None) => {
+                    warn!("player eliminated due to reversing without a previous player");
+                    break 'eliminate EliminationReason::SpecialUnavailable;
+                }
+            };
+
             if !self.players.contains(next) {
                 warn!("player eliminated due to invalid next player");
-                break 'eliminate true;
+                break 'eliminate EliminationReason::InvalidTarget;
+            }
+
+            if !reversed && self.no_teammate_targeting && self.are_teammates(pid, next) {
+                warn!("player eliminated due to targeting a teammate");
+                break 'eliminate EliminationReason::TargetedTeammate;
+            }
+
+            if !reversed && self.rules.no_bounce_back && prev == Some(next) && self.players.len() > 2 {
+                warn!("player eliminated due to bouncing back");
+                break 'eliminate EliminationReason::BouncedBack;
+            }
+
+            if !reversed && self.rules.neighbours_only && !self.are_neighbours(pid, next) {
+                warn!("player eliminated due to targeting a non-neighbour");
+                break 'eliminate EliminationReason::NotNeighbour;
+            }
+
+            if let Some(special) = special {
+                let remaining = &mut self.specials[pid];
+                *remaining -= 1;
+                let remaining = *remaining;
+                self.tokens[token] = Token { curr: next, prev: Some(pid), action: expected };
+                info!(?special, remaining, "special action played");
+                return TickResult::Special { special, next, remaining };
             }
 
-            if action != self.action {
-                warn!(action = ?self.action, "player eliminated due to unexpected action");
-                break 'eliminate true;
+            let expected = &self.actions[expected];
+            if action != *expected {
+                warn!(%expected, "player eliminated due to unexpected action");
+                break 'eliminate EliminationReason::WrongAction { expected: expected.clone(), actual: action };
             }
 
-            self.curr = next;
-            self.action = self.action.next();
+            let count = self.actions.len();
+            let state = &mut self.tokens[token];
+            *state = Token { curr: next, prev: Some(pid), action: (state.action + 1) % count };
 
             info!("successful transition to next turn");
             return TickResult::Proceed;
         };
 
+        self.eliminate(pid, reason)
+    }
+
+    /// Eliminates the holder of the `token` for failing to respond in time.
+    #[instrument]
+    pub fn timeout(&mut self, token: usize) -> TickResult<Player> {
+        let curr = self.tokens[token].curr;
+        warn!(curr, "player eliminated due to timeout");
+        self.eliminate(curr, EliminationReason::Timeout)
+    }
+
+    /// Gracefully eliminates the player (e.g., due to a disconnection) regardless of their remaining lives.
+    #[instrument]
+    pub fn forfeit(&mut self, pid: usize) -> TickResult<Player> {
+        if !self.players.contains(pid) {
+            warn!("player does not exist in the game");
+            return TickResult::NoOp;
+        }
+        warn!("player eliminated due to graceful elimination");
+        TickResult::Eliminated(self.remove(pid), EliminationReason::TargetedSelf)
+    }
+
+    /// Costs the player a life. If the player survives their mistake, they must start over at Zip with every potato
+    /// they hold.
+    fn eliminate(&mut self, pid: usize, reason: EliminationReason) -> TickResult<Player> {
+        let lives = &mut self.lives[pid];
+        *lives = lives.saturating_sub(1);
+        if *lives > 0 {
+            let remaining = *lives;
+            info!(remaining, "player lost a life");
+            for token in self.tokens.iter_mut().filter(|token| token.curr == pid) {
+                *token = Token::new(pid);
+            }
+            return TickResult::LifeLost { reason, remaining };
+        }
+
+        TickResult::Eliminated(self.remove(pid), reason)
+    }
+
+    /// Removes the player from the game regardless of their remaining lives.
+    pub fn disqualify(&mut self, pid: usize) -> Option<Player> {
+        self.players.contains(pid).then(|| self.remove(pid))
+    }
+
+    /// Removes the player from the game, handing their potatoes to the remaining players at random.
+    fn remove(&mut self, pid: usize) -> Player {
+        self.lives.remove(pid);
+        self.specials.try_remove(pid);
+        self.teams.try_remove(pid);
+        self.seats.retain(|&other| other != pid);
         let result = self.players.remove(pid);
 
-        if must_reassign {
-            let (next, _) = self.players.iter().next().expect("at least one player must be present");
-            self.curr = next;
+        for token in 0..self.tokens.len() {
+            if self.tokens[token].prev == Some(pid) {
+                self.tokens[token].prev = None;
+            }
+            if self.tokens[token].curr == pid {
+                let curr = self.random_player().expect("at least one player must be present");
+                self.tokens[token].curr = curr;
+                self.tokens[token].prev = None;
+            }
         }
 
-        TickResult::Eliminated(result)
+        result
     }
 }
@@ -0,0 +1,83 @@
+use crate::event::game::{EliminationReason, Standing};
+use core::time::Duration;
+use jiff::Timestamp;
+use std::collections::BTreeMap;
+
+/// Record of a player who has been eliminated from the game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Elimination {
+    /// The ID of the eliminated player.
+    pub pid: usize,
+    /// The number of successful rounds since the previous elimination.
+    pub round: u32,
+    /// The number of successful rounds since the start of the game.
+    pub rounds_survived: u32,
+    pub reason: EliminationReason,
+    pub eliminated_at: Timestamp,
+}
+
+/// Progress of the game across rounds, which is used for pacing and the final standings.
+#[derive(Debug, Default)]
+pub struct GameRecord {
+    /// The number of successful rounds since the last elimination.
+    pub round: u32,
+    /// The number of successful rounds since the start of the game.
+    pub total_rounds: u32,
+    /// Eliminated players in the order of their elimination.
+    pub history: Vec<Elimination>,
+    /// Total time and count of the successful responses of each player.
+    responses: BTreeMap<usize, (Duration, u32)>,
+}
+
+impl GameRecord {
+    /// Records a successful transition to the next round.
+    pub fn proceed(&mut self) {
+        self.round += 1;
+        self.total_rounds += 1;
+    }
+
+    /// Records the elimination of a player, which resets the current round.
+    pub fn eliminate(&mut self, pid: usize, reason: EliminationReason, eliminated_at: Timestamp) {
+        let Self { round, total_rounds, .. } = *self;
+        self.history.push(Elimination { pid, round, rounds_survived: total_rounds, reason, eliminated_at });
+        self.round = 0;
+    }
+
+    /// Records how long the player took to respond successfully.
+    pub fn respond(&mut self, pid: usize, elapsed: Duration) {
+        let (total, count) = self.responses.entry(pid).or_default();
+        *total += elapsed;
+        *count += 1;
+    }
+
+    /// The average time that the player took to respond successfully (if ever).
+    pub fn average_response(&self, pid: usize) -> Option<Duration> {
+        self.responses.get(&pid).map(|&(total, count)| total / count)
+    }
+
+    /// Orders the `survivors` from the fastest to the slowest average response. Players who have never responded
+    /// successfully come last.
+    pub fn tiebreak(&self, survivors: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut ranking: Vec<_> = survivors.into_iter().collect();
+        ranking
+            .sort_by_key(|&pid| self.average_response(pid).map_or((true, Duration::ZERO), |average| (false, average)));
+        ranking
+    }
+
+    /// Ranks the `winners` first (tied), followed by the eliminated players in the reverse order of their elimination.
+    pub fn standings(&self, winners: &[usize]) -> Vec<Standing> {
+        let rounds_survived = self.total_rounds;
+        let first =
+            winners.iter().map(|&pid| Standing { pid, place: 1, rounds_survived, reason: None, eliminated_at: None });
+        let rest = self.history.iter().rev().zip(winners.len() + 1..).map(
+            |(Elimination { pid, rounds_survived, reason, eliminated_at, .. }, place)| Standing {
+                pid: *pid,
+                place,
+                rounds_survived: *rounds_survived,
+                reason: Some(reason.clone()),
+                eliminated_at: Some(*eliminated_at),
+            },
+        );
+        first.chain(rest).collect()
+    }
+}
@@ -0,0 +1,62 @@
+use crate::event::lobby::SuddenDeathSettings;
+use core::time::Duration;
+use std::time::Instant;
+
+/// Phase of a game that has exceeded its round or time limit.
+#[derive(Clone, Copy, Debug)]
+pub struct SuddenDeath {
+    /// Successful rounds after which sudden death begins (if limited).
+    max_rounds: Option<u32>,
+    /// Time after the start of the game at which sudden death begins (if limited).
+    max_duration: Option<Duration>,
+    /// Fixed deadline of every turn during sudden death.
+    pub deadline: Duration,
+    /// Successful rounds of sudden death before the winner is declared by tiebreak.
+    pub rounds: u32,
+    started_at: Instant,
+    /// The total number of successful rounds when sudden death began.
+    began_at: Option<u32>,
+}
+
+impl SuddenDeath {
+    pub fn new(settings: &SuddenDeathSettings, started_at: Instant) -> Self {
+        let SuddenDeathSettings { max_rounds, max_duration, deadline, rounds } = *settings;
+        Self {
+            max_rounds: (max_rounds > 0).then_some(max_rounds),
+            max_duration: (max_duration > 0.0).then(|| Duration::from_secs_f64(max_duration)),
+            deadline: Duration::from_secs_f64(deadline),
+            rounds,
+            started_at,
+            began_at: None,
+        }
+    }
+
+    /// Excludes the time during which the game was paused from the time limit.
+    pub fn postpone(&mut self, paused: Duration) {
+        self.started_at += paused;
+    }
+
+    /// Whether the game is currently in sudden death.
+    pub const fn is_active(&self) -> bool {
+        self.began_at.is_some()
+    }
+
+    /// Enters sudden death if the game has just reached one of its limits.
+    pub fn begin(&mut self, total_rounds: u32, now: Instant) -> bool {
+        if self.is_active() {
+            return false;
+        }
+        let rounds_exceeded = self.max_rounds.is_some_and(|max| total_rounds >= max);
+        let time_exceeded = self.max_duration.is_some_and(|max| now.duration_since(self.started_at) >= max);
+        if !rounds_exceeded && !time_exceeded {
+            return false;
+        }
+        self.began_at = Some(total_rounds);
+        true
+    }
+
+    /// Whether sudden death has lasted long enough to declare the winner by tiebreak.
+    pub fn is_over(&self, total_rounds: u32) -> bool {
+        self.began_at.is_some_and(|began_at| total_rounds - began_at >= self.rounds)
+    }
+}
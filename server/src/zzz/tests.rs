@@ -1,8 +1,26 @@
 use crate::{
-    event::player::{PlayerAction, PlayerResponds},
-    zzz::{TickResult, ZipZapZop},
+    actor::game::handle_game,
+    event::{
+        game::{EliminationReason, GameCommand, RemainingPlayer},
+        lobby::{BotSkill, BotStrategy, DeadlinePolicyKind, GameSettings, SuddenDeathSettings, TargetingRules},
+        player::{PlayerEvent, PlayerResponds, PlayerRespondsWithId, SpecialAction},
+    },
+    router::{game::GameProgress, lobby::is_valid_settings},
+    zzz::{
+        bot::Bot,
+        deadline::{DeadlinePolicy, Exponential, Fixed, FloorWithDecay, Linear},
+        record::GameRecord,
+        sudden_death::SuddenDeath,
+        TickResult, Winner, ZipZapZop,
+    },
 };
+use arcstr::ArcStr;
+use core::time::Duration;
+use jiff::Timestamp;
 use slab::Slab;
+use std::time::Instant;
+use tokio::sync::{broadcast, mpsc, watch};
+use triomphe::Arc;
 
 #[test]
 fn non_existent_player_should_noop() {
@@ -10,10 +28,16 @@ fn non_existent_player_should_noop() {
     let pid = players.insert(());
     let key = players.vacant_key();
 
-    let mut zzz = ZipZapZop::new(players, pid);
-    assert_eq!(zzz.tick(PlayerResponds { pid: key, next: key, action: PlayerAction::Zip }), TickResult::NoOp);
-    assert_eq!(zzz.curr, pid);
-    assert_eq!(zzz.action, PlayerAction::Zip);
+    let mut zzz = ZipZapZop::new(players, pid, 0);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: key,
+            data: PlayerResponds { token: 0, next: key, action: "Zip".into(), special: None }
+        }),
+        TickResult::NoOp
+    );
+    assert_eq!(zzz.tokens[0].curr, pid);
+    assert_eq!(zzz.tokens[0].action, 0);
     assert_eq!(zzz.players.len(), 1);
     assert_eq!(zzz.players.get(pid).copied(), Some(()));
 }
@@ -24,13 +48,16 @@ fn non_curr_player_should_be_eliminated() {
     let curr = players.insert("curr");
     let next = players.insert("next");
 
-    let mut zzz = ZipZapZop::new(players, curr);
+    let mut zzz = ZipZapZop::new(players, curr, 0);
     assert_eq!(
-        zzz.tick(PlayerResponds { pid: next, next: curr, action: PlayerAction::Zip }),
-        TickResult::Eliminated("next")
+        zzz.tick(PlayerRespondsWithId {
+            pid: next,
+            data: PlayerResponds { token: 0, next: curr, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated("next", EliminationReason::NotTheirTurn)
     );
-    assert_eq!(zzz.curr, curr);
-    assert_eq!(zzz.action, PlayerAction::Zip);
+    assert_eq!(zzz.tokens[0].curr, curr);
+    assert_eq!(zzz.tokens[0].action, 0);
     assert_eq!(zzz.players.len(), 1);
     assert_eq!(zzz.players.get(next), None);
 }
@@ -41,13 +68,16 @@ fn curr_player_graceful_elimination() {
     let curr = players.insert("curr");
     let next = players.insert("next");
 
-    let mut zzz = ZipZapZop::new(players, curr);
+    let mut zzz = ZipZapZop::new(players, curr, 0);
     assert_eq!(
-        zzz.tick(PlayerResponds { pid: curr, next: curr, action: PlayerAction::Zip }),
-        TickResult::Eliminated("curr")
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next: curr, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated("curr", EliminationReason::TargetedSelf)
     );
-    assert_eq!(zzz.curr, next);
-    assert_eq!(zzz.action, PlayerAction::Zip);
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 0);
     assert_eq!(zzz.players.len(), 1);
     assert_eq!(zzz.players.get(curr), None);
 }
@@ -59,13 +89,16 @@ fn curr_player_should_be_eliminated_from_valid_lobby_for_non_existent_next() {
     let next = players.insert("next");
     let key = players.vacant_key();
 
-    let mut zzz = ZipZapZop::new(players, curr);
+    let mut zzz = ZipZapZop::new(players, curr, 0);
     assert_eq!(
-        zzz.tick(PlayerResponds { pid: curr, next: key, action: PlayerAction::Zip }),
-        TickResult::Eliminated("curr")
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next: key, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated("curr", EliminationReason::InvalidTarget)
     );
-    assert_eq!(zzz.curr, next);
-    assert_eq!(zzz.action, PlayerAction::Zip);
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 0);
     assert_eq!(zzz.players.len(), 1);
     assert_eq!(zzz.players.get(curr), None);
 }
@@ -76,10 +109,16 @@ fn curr_player_should_be_eliminated_from_valid_lobby_for_unexpected_action() {
     let curr = players.insert("curr");
     let next = players.insert("next");
 
-    let mut zzz = ZipZapZop::new(players, curr);
-    assert_eq!(zzz.tick(PlayerResponds { pid: curr, next, action: PlayerAction::Zap }), TickResult::Eliminated("curr"));
-    assert_eq!(zzz.curr, next);
-    assert_eq!(zzz.action, PlayerAction::Zip);
+    let mut zzz = ZipZapZop::new(players, curr, 0);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zap".into(), special: None }
+        }),
+        TickResult::Eliminated("curr", EliminationReason::WrongAction { expected: "Zip".into(), actual: "Zap".into() })
+    );
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 0);
     assert_eq!(zzz.players.len(), 1);
     assert_eq!(zzz.players.get(curr), None);
 }
@@ -90,10 +129,16 @@ fn successful_transition() {
     let curr = players.insert("curr");
     let next = players.insert("next");
 
-    let mut zzz = ZipZapZop::new(players, curr);
-    assert_eq!(zzz.tick(PlayerResponds { pid: curr, next, action: PlayerAction::Zip }), TickResult::Proceed);
-    assert_eq!(zzz.curr, next);
-    assert_eq!(zzz.action, PlayerAction::Zap);
+    let mut zzz = ZipZapZop::new(players, curr, 0);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 1);
     assert_eq!(zzz.players.len(), 2);
     assert_eq!(zzz.players.get(curr).copied(), Some("curr"));
     assert_eq!(zzz.players.get(next).copied(), Some("next"));
@@ -104,26 +149,756 @@ fn multiple_successful_transitions() {
     let mut players = Slab::new();
     let curr = players.insert("curr");
     let next = players.insert("next");
-    let mut zzz = ZipZapZop::new(players, curr);
+    let mut zzz = ZipZapZop::new(players, curr, 0);
 
-    assert_eq!(zzz.tick(PlayerResponds { pid: curr, next, action: PlayerAction::Zip }), TickResult::Proceed);
-    assert_eq!(zzz.curr, next);
-    assert_eq!(zzz.action, PlayerAction::Zap);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 1);
     assert_eq!(zzz.players.len(), 2);
     assert_eq!(zzz.players.get(curr).copied(), Some("curr"));
     assert_eq!(zzz.players.get(next).copied(), Some("next"));
 
-    assert_eq!(zzz.tick(PlayerResponds { pid: next, next: curr, action: PlayerAction::Zap }), TickResult::Proceed);
-    assert_eq!(zzz.curr, curr);
-    assert_eq!(zzz.action, PlayerAction::Zop);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: next,
+            data: PlayerResponds { token: 0, next: curr, action: "Zap".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(zzz.tokens[0].curr, curr);
+    assert_eq!(zzz.tokens[0].action, 2);
     assert_eq!(zzz.players.len(), 2);
     assert_eq!(zzz.players.get(curr).copied(), Some("curr"));
     assert_eq!(zzz.players.get(next).copied(), Some("next"));
 
-    assert_eq!(zzz.tick(PlayerResponds { pid: curr, next, action: PlayerAction::Zop }), TickResult::Proceed);
-    assert_eq!(zzz.curr, next);
-    assert_eq!(zzz.action, PlayerAction::Zip);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zop".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 0);
     assert_eq!(zzz.players.len(), 2);
     assert_eq!(zzz.players.get(curr).copied(), Some("curr"));
     assert_eq!(zzz.players.get(next).copied(), Some("next"));
 }
+
+#[test]
+fn fixed_deadline_never_changes() {
+    let policy = Fixed(Duration::from_secs(3));
+    assert_eq!(policy.deadline(0, 2, &[]), Duration::from_secs(3));
+    assert_eq!(policy.deadline(100, 2, &[]), Duration::from_secs(3));
+}
+
+#[test]
+fn exponential_deadline_decays() {
+    let policy = Exponential { initial: Duration::from_secs(6), decay_rate: 0.5 };
+    assert_eq!(policy.deadline(0, 2, &[]), Duration::from_secs(6));
+    assert!(policy.deadline(1, 2, &[]) < Duration::from_secs(4));
+    assert!(policy.deadline(100, 2, &[]) < Duration::from_millis(1));
+}
+
+#[test]
+fn linear_deadline_stops_at_floor() {
+    let policy =
+        Linear { initial: Duration::from_secs(6), step: Duration::from_secs(1), floor: Duration::from_secs(2) };
+    assert_eq!(policy.deadline(0, 2, &[]), Duration::from_secs(6));
+    assert_eq!(policy.deadline(3, 2, &[]), Duration::from_secs(3));
+    assert_eq!(policy.deadline(4, 2, &[]), Duration::from_secs(2));
+    assert_eq!(policy.deadline(100, 2, &[]), Duration::from_secs(2));
+}
+
+#[test]
+fn decaying_deadline_stops_at_floor() {
+    let decay = Exponential { initial: Duration::from_secs(6), decay_rate: 0.5 };
+    let policy = FloorWithDecay { decay, floor: Duration::from_secs(1) };
+    assert_eq!(policy.deadline(0, 2, &[]), Duration::from_secs(6));
+    assert_eq!(policy.deadline(100, 2, &[]), Duration::from_secs(1));
+}
+
+#[test]
+fn deadline_policies_follow_their_own_settings() {
+    let settings = GameSettings {
+        deadline_policy: DeadlinePolicyKind::Linear,
+        deadline_step: 2.0,
+        min_deadline: 1.0,
+        ..Default::default()
+    };
+    assert!(is_valid_settings(&settings));
+    let policy = Box::<dyn DeadlinePolicy>::from(&settings);
+    assert_eq!(policy.deadline(1, 2, &[]), Duration::from_secs(4));
+    assert_eq!(policy.deadline(3, 2, &[]), Duration::from_secs(1));
+
+    // Only some of the policies have a floor
+    let settings = GameSettings { deadline_policy: DeadlinePolicyKind::Exponential, ..settings };
+    assert!(!is_valid_settings(&settings));
+    assert!(is_valid_settings(&GameSettings { min_deadline: 0.0, ..settings }));
+}
+
+#[test]
+fn random_start_is_reproducible() {
+    let players: Slab<_> = (0..8).map(|pid| (pid, ())).collect();
+    let first = ZipZapZop::with_random_start(players.clone(), 42);
+    let second = ZipZapZop::with_random_start(players, 42);
+    assert_eq!(first.tokens[0].curr, second.tokens[0].curr);
+    assert!(first.players.contains(first.tokens[0].curr));
+}
+
+#[test]
+fn graceful_elimination_reassigns_to_remaining_player() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    players.insert("a");
+    players.insert("b");
+
+    let mut zzz = ZipZapZop::new(players, curr, 7);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next: curr, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated("curr", EliminationReason::TargetedSelf)
+    );
+    assert_ne!(zzz.tokens[0].curr, curr);
+    assert!(zzz.players.contains(zzz.tokens[0].curr));
+    assert_eq!(zzz.tokens[0].action, 0);
+}
+
+#[test]
+fn timeout_eliminates_curr_player() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0);
+    assert_eq!(zzz.timeout(0), TickResult::Eliminated("curr", EliminationReason::Timeout));
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.players.len(), 1);
+    assert_eq!(zzz.players.get(curr), None);
+}
+
+#[test]
+fn non_curr_player_may_forfeit() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0);
+    assert_eq!(zzz.forfeit(next), TickResult::Eliminated("next", EliminationReason::TargetedSelf));
+    assert_eq!(zzz.tokens[0].curr, curr);
+    assert_eq!(zzz.players.len(), 1);
+}
+
+#[test]
+fn standings_rank_latest_eliminations_first() {
+    let mut record = GameRecord::default();
+    record.proceed();
+    record.eliminate(3, EliminationReason::Timeout, Timestamp::UNIX_EPOCH);
+    record.proceed();
+    record.proceed();
+    record.eliminate(1, EliminationReason::NotTheirTurn, Timestamp::UNIX_EPOCH);
+    record.proceed();
+    assert_eq!(record.round, 1);
+
+    let standings = record.standings(&[2]);
+    let ranks: Vec<_> =
+        standings.iter().map(|standing| (standing.pid, standing.place, standing.rounds_survived)).collect();
+    assert_eq!(ranks, [(2, 1, 4), (1, 2, 3), (3, 3, 1)]);
+    assert_eq!(standings[0].reason, None);
+    assert_eq!(standings[1].reason, Some(EliminationReason::NotTheirTurn));
+}
+
+#[test]
+fn mistakes_cost_lives_before_elimination() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_lives(2);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: next,
+            data: PlayerResponds { token: 0, next: curr, action: "Zop".into(), special: None }
+        }),
+        TickResult::LifeLost {
+            reason: EliminationReason::WrongAction { expected: "Zap".into(), actual: "Zop".into() },
+            remaining: 1
+        }
+    );
+    assert_eq!(zzz.tokens[0].curr, next);
+    assert_eq!(zzz.tokens[0].action, 0);
+    assert_eq!(zzz.players.len(), 2);
+
+    assert_eq!(zzz.timeout(0), TickResult::Eliminated("next", EliminationReason::Timeout));
+    assert_eq!(zzz.tokens[0].curr, curr);
+    assert_eq!(zzz.players.len(), 1);
+}
+
+#[test]
+fn graceful_elimination_ignores_lives() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_lives(3);
+    assert_eq!(zzz.forfeit(curr), TickResult::Eliminated("curr", EliminationReason::TargetedSelf));
+    assert_eq!(zzz.players.len(), 1);
+}
+
+#[test]
+fn targeting_self_costs_a_life() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_lives(3);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next: curr, action: "Zip".into(), special: None }
+        }),
+        TickResult::LifeLost { reason: EliminationReason::TargetedSelf, remaining: 2 }
+    );
+    assert_eq!(zzz.tokens[0].curr, curr);
+    assert_eq!(zzz.players.len(), 2);
+}
+
+/// Waits for the next game event of the given type. Nobody may be eliminated on the way there.
+async fn next_event(broadcast_rx: &mut broadcast::Receiver<Arc<[u8]>>, kind: &str) -> serde_json::Value {
+    loop {
+        let bytes = broadcast_rx.recv().await.unwrap();
+        let event: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+        match event["type"].as_str().unwrap() {
+            found if found == kind => return event,
+            "GameEliminated" | "GameLifeLost" => panic!("unexpected elimination: {event}"),
+            _ => continue,
+        }
+    }
+}
+
+#[tokio::test]
+async fn paused_game_only_processes_forfeits() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+    let gone = players.insert("gone");
+
+    let (event_tx, mut event_rx) = mpsc::channel(4);
+    let (command_tx, mut command_rx) = mpsc::channel(1);
+    let (broadcast_tx, mut broadcast_rx) = broadcast::channel(16);
+    let (progress_tx, _) = watch::channel(GameProgress::default());
+    let mut zzz = ZipZapZop::new(players, curr, 0);
+    let sudden_death = SuddenDeath::new(&SuddenDeathSettings::default(), Instant::now());
+    let policy = Fixed(Duration::from_secs(60));
+    let game =
+        handle_game(&mut event_rx, &mut command_rx, &broadcast_tx, &progress_tx, &mut zzz, &policy, sudden_death);
+
+    let driver = async {
+        command_tx.send(GameCommand::GamePause).await.unwrap();
+        next_event(&mut broadcast_rx, "GamePaused").await;
+
+        // Pointing to themselves is a mistake, which is not penalized while paused
+        let data = PlayerResponds { token: 0, next, action: "Zip".into(), special: None };
+        event_tx.send(PlayerEvent::Responds(PlayerRespondsWithId { pid: next, data })).await.unwrap();
+        event_tx.send(PlayerEvent::Forfeit(gone)).await.unwrap();
+        let eliminated = next_event(&mut broadcast_rx, "GameEliminated").await;
+        assert_eq!(eliminated["pid"], gone);
+
+        command_tx.send(GameCommand::GameResume).await.unwrap();
+        next_event(&mut broadcast_rx, "GameResumed").await;
+        event_tx.send(PlayerEvent::Forfeit(curr)).await.unwrap();
+        next_event(&mut broadcast_rx, "GameEliminated").await;
+        next_event(&mut broadcast_rx, "GameConcluded").await
+    };
+
+    let ((), concluded) = tokio::join!(game, driver);
+    assert_eq!(concluded["pid"], next);
+    assert!(matches!(zzz.winner(), Ok(Winner::Player(pid)) if pid == next));
+}
+
+#[test]
+fn last_team_standing_wins() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let mate = players.insert("mate");
+    let rival = players.insert("rival");
+    let teams = [(curr, 0), (mate, 0), (rival, 1)].into_iter().collect();
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_teams(teams, false);
+    assert!(zzz.winner().is_err());
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next: mate, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(zzz.timeout(0), TickResult::Eliminated("mate", EliminationReason::Timeout));
+    assert!(zzz.winner().is_err());
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: rival,
+            data: PlayerResponds { token: 0, next: rival, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated("rival", EliminationReason::TargetedSelf)
+    );
+    assert!(matches!(zzz.winner(), Ok(Winner::Team(0))));
+}
+
+#[test]
+fn teammate_targeting_may_be_forbidden() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let mate = players.insert("mate");
+    let rival = players.insert("rival");
+    let teams = [(curr, 0), (mate, 0), (rival, 1)].into_iter().collect();
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_teams(teams, true);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next: mate, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated("curr", EliminationReason::TargetedTeammate)
+    );
+    assert_eq!(zzz.players.len(), 2);
+}
+
+#[test]
+fn custom_actions_cycle_through_vocabulary() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+    let actions = ["Zip", "Zap", "Zop", "Zoom"].into_iter().map(ArcStr::from).collect();
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_actions(actions);
+    for (pid, target, action) in [(curr, next, "Zip"), (next, curr, "Zap"), (curr, next, "Zop"), (next, curr, "Zoom")] {
+        assert_eq!(
+            zzz.tick(PlayerRespondsWithId {
+                pid,
+                data: PlayerResponds { token: 0, next: target, action: action.into(), special: None }
+            }),
+            TickResult::Proceed
+        );
+    }
+    assert_eq!(zzz.tokens[0].action, 0);
+    assert_eq!(zzz.expects(0, Timestamp::UNIX_EPOCH).action, "Zip");
+
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zoom".into(), special: None }
+        }),
+        TickResult::Eliminated(
+            "curr",
+            EliminationReason::WrongAction { expected: "Zip".into(), actual: "Zoom".into() }
+        )
+    );
+}
+
+#[test]
+fn bounce_back_may_be_forbidden() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+    let other = players.insert("other");
+    let rules = TargetingRules { no_bounce_back: true, ..Default::default() };
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_rules(rules);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: next,
+            data: PlayerResponds { token: 0, next: curr, action: "Zap".into(), special: None }
+        }),
+        TickResult::Eliminated("next", EliminationReason::BouncedBack)
+    );
+
+    // Bouncing back is the only option left between the last two players
+    let curr = zzz.tokens[0].curr;
+    let next = if curr == other { zzz.survivors().find(|&pid| pid != other).unwrap() } else { other };
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: "Zap".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: next,
+            data: PlayerResponds { token: 0, next: curr, action: "Zop".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+}
+
+#[test]
+fn neighbours_only_wraps_around_the_circle() {
+    let mut players = Slab::new();
+    let first = players.insert("first");
+    let second = players.insert("second");
+    let third = players.insert("third");
+    let fourth = players.insert("fourth");
+    let rules = TargetingRules { neighbours_only: true, ..Default::default() };
+
+    let mut zzz = ZipZapZop::new(players, first, 0).with_rules(rules);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: first,
+            data: PlayerResponds { token: 0, next: fourth, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: fourth,
+            data: PlayerResponds { token: 0, next: second, action: "Zap".into(), special: None }
+        }),
+        TickResult::Eliminated("fourth", EliminationReason::NotNeighbour)
+    );
+
+    // The circle closes the gap left by the eliminated player
+    zzz.tokens[0].curr = third;
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: third,
+            data: PlayerResponds { token: 0, next: first, action: "Zap".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+}
+
+#[test]
+fn circle_is_ordered_by_seat_rather_than_player_id() {
+    let mut players = Slab::new();
+    let first = players.insert("first");
+    let second = players.insert("second");
+    let third = players.insert("third");
+    let fourth = players.insert("fourth");
+    let rules = TargetingRules { neighbours_only: true, ..Default::default() };
+
+    // The second player took over a reused player ID after the third player had joined
+    let mut zzz = ZipZapZop::new(players, first, 0).with_rules(rules).with_seats([first, third, second, fourth]);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: first,
+            data: PlayerResponds { token: 0, next: third, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: third,
+            data: PlayerResponds { token: 0, next: fourth, action: "Zap".into(), special: None }
+        }),
+        TickResult::Eliminated("third", EliminationReason::NotNeighbour)
+    );
+
+    let skill = BotSkill { mistake_rate: 0.0, strategy: BotStrategy::Clockwise, ..Default::default() };
+    let players = vec![(first, None), (third, None), (second, None), (fourth, None)];
+    let mut bot = Bot::new(third, skill, players, &GameSettings::default(), 0);
+    assert_eq!(bot.respond(0, "Zip".into()).next, second);
+    bot.eliminate(second);
+    assert_eq!(bot.respond(0, "Zip".into()).next, fourth);
+}
+
+#[test]
+fn skip_passes_the_expected_action_on() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_specials(1);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: ArcStr::new(), special: Some(SpecialAction::Skip) }
+        }),
+        TickResult::Special { special: SpecialAction::Skip, next, remaining: 0 }
+    );
+    assert_eq!(zzz.expects(0, Timestamp::UNIX_EPOCH).action, "Zip");
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: next,
+            data: PlayerResponds { token: 0, next: curr, action: ArcStr::new(), special: Some(SpecialAction::Skip) }
+        }),
+        TickResult::Special { special: SpecialAction::Skip, next: curr, remaining: 0 }
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: curr,
+            data: PlayerResponds { token: 0, next, action: ArcStr::new(), special: Some(SpecialAction::Skip) }
+        }),
+        TickResult::Eliminated("curr", EliminationReason::SpecialUnavailable)
+    );
+}
+
+#[test]
+fn reverse_returns_the_turn_to_the_previous_player() {
+    let mut players = Slab::new();
+    let first = players.insert("first");
+    let second = players.insert("second");
+    let third = players.insert("third");
+    let rules = TargetingRules { no_bounce_back: true, ..Default::default() };
+
+    let mut zzz = ZipZapZop::new(players, first, 0).with_rules(rules).with_specials(1);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: first,
+            data: PlayerResponds {
+                token: 0,
+                next: first,
+                action: ArcStr::new(),
+                special: Some(SpecialAction::Reverse)
+            }
+        }),
+        TickResult::Eliminated("first", EliminationReason::SpecialUnavailable)
+    );
+
+    zzz.tokens[0].curr = second;
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: second,
+            data: PlayerResponds { token: 0, next: third, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: third,
+            data: PlayerResponds {
+                token: 0,
+                next: third,
+                action: ArcStr::new(),
+                special: Some(SpecialAction::Reverse)
+            }
+        }),
+        TickResult::Special { special: SpecialAction::Reverse, next: second, remaining: 0 }
+    );
+    assert_eq!(zzz.expects(0, Timestamp::UNIX_EPOCH).action, "Zap");
+}
+
+#[test]
+fn potatoes_are_handed_to_distinct_players() {
+    let players: Slab<_> = (0..8).map(|pid| (pid, ())).collect();
+    let zzz = ZipZapZop::with_random_start(players, 42).with_potatoes(3);
+    assert_eq!(zzz.tokens(), 3);
+    let holders: Vec<_> = (0..3).map(|token| zzz.holder(token)).collect();
+    assert!(holders.iter().enumerate().all(|(i, pid)| !holders[..i].contains(pid)));
+}
+
+#[test]
+fn potatoes_cycle_independently() {
+    let mut players = Slab::new();
+    let first = players.insert("first");
+    let second = players.insert("second");
+    let third = players.insert("third");
+
+    let mut zzz = ZipZapZop::new(players, first, 0).with_potatoes(2);
+    let other = zzz.holder(1);
+    assert_ne!(other, first);
+    let target = if other == second { third } else { second };
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: other,
+            data: PlayerResponds { token: 1, next: target, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+    assert_eq!(zzz.expects(0, Timestamp::UNIX_EPOCH).action, "Zip");
+    assert_eq!(zzz.expects(1, Timestamp::UNIX_EPOCH).action, "Zap");
+
+    // Passing a potato that one does not hold is a mistake
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: target,
+            data: PlayerResponds { token: 0, next: first, action: "Zip".into(), special: None }
+        }),
+        TickResult::Eliminated(if target == second { "second" } else { "third" }, EliminationReason::NotTheirTurn)
+    );
+    assert_eq!(zzz.holder(0), first);
+    assert_ne!(zzz.holder(1), target);
+    assert!(zzz.players.contains(zzz.holder(1)));
+}
+
+#[test]
+fn mistakes_only_restart_the_affected_potatoes() {
+    let mut players = Slab::new();
+    let first = players.insert("first");
+    let second = players.insert("second");
+    let third = players.insert("third");
+
+    let mut zzz = ZipZapZop::new(players, first, 0).with_lives(2).with_potatoes(2);
+    let holder = zzz.holder(1);
+    let target = if holder == second { third } else { second };
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: holder,
+            data: PlayerResponds { token: 1, next: target, action: "Zap".into(), special: None }
+        }),
+        TickResult::LifeLost {
+            reason: EliminationReason::WrongAction { expected: "Zip".into(), actual: "Zap".into() },
+            remaining: 1
+        }
+    );
+    assert!(!zzz.is_restarted(0, first, holder));
+    assert!(zzz.is_restarted(1, holder, holder));
+
+    // The potato of the eliminated player changes hands, but the other potato keeps its deadline
+    assert_eq!(
+        zzz.timeout(1),
+        TickResult::Eliminated(if holder == second { "second" } else { "third" }, EliminationReason::Timeout)
+    );
+    assert!(!zzz.is_restarted(0, first, holder));
+    assert_ne!(zzz.holder(1), holder);
+    assert!(zzz.is_restarted(1, holder, holder));
+}
+
+#[test]
+fn snapshot_skips_unannounced_potatoes() {
+    let mut players = Slab::new();
+    let curr = players.insert("curr");
+    let next = players.insert("next");
+
+    let mut zzz = ZipZapZop::new(players, curr, 0).with_lives(2).with_potatoes(2);
+    assert_eq!(
+        zzz.tick(PlayerRespondsWithId {
+            pid: zzz.holder(0),
+            data: PlayerResponds { token: 0, next, action: "Zip".into(), special: None }
+        }),
+        TickResult::Proceed
+    );
+
+    let snapshot = zzz.snapshot(&[Some(Timestamp::UNIX_EPOCH), None], 3, true);
+    assert_eq!(snapshot.round, 3);
+    assert!(snapshot.paused);
+    assert_eq!(
+        snapshot.players,
+        [
+            RemainingPlayer { pid: curr, player: "curr".into(), lives: 2, team: None },
+            RemainingPlayer { pid: next, player: "next".into(), lives: 2, team: None },
+        ]
+    );
+    assert_eq!(snapshot.expected, [zzz.expects(0, Timestamp::UNIX_EPOCH)]);
+    assert_eq!(snapshot.expected[0].next, next);
+    assert_eq!(snapshot.expected[0].action, "Zap");
+}
+
+#[test]
+fn sudden_death_begins_at_round_limit() {
+    let settings = SuddenDeathSettings { max_rounds: 10, rounds: 3, ..Default::default() };
+    let now = Instant::now();
+    let mut sudden_death = SuddenDeath::new(&settings, now);
+    assert!(!sudden_death.begin(9, now));
+    assert!(sudden_death.begin(10, now));
+    assert!(sudden_death.is_active());
+    assert!(!sudden_death.begin(11, now));
+    assert!(!sudden_death.is_over(12));
+    assert!(sudden_death.is_over(13));
+}
+
+#[test]
+fn sudden_death_begins_at_time_limit() {
+    let settings = SuddenDeathSettings { max_duration: 60.0, ..Default::default() };
+    let now = Instant::now();
+    let mut sudden_death = SuddenDeath::new(&settings, now);
+    assert!(!sudden_death.begin(1000, now));
+    assert!(sudden_death.begin(1000, now + Duration::from_secs(60)));
+
+    let mut unlimited = SuddenDeath::new(&SuddenDeathSettings::default(), now);
+    assert!(!unlimited.begin(u32::MAX, now + Duration::from_secs(3600)));
+}
+
+#[test]
+fn tiebreak_favours_fastest_average_response() {
+    let mut record = GameRecord::default();
+    record.respond(0, Duration::from_millis(900));
+    record.respond(0, Duration::from_millis(100));
+    record.respond(1, Duration::from_millis(400));
+    record.respond(3, Duration::from_millis(600));
+    assert_eq!(record.average_response(0), Some(Duration::from_millis(500)));
+    assert_eq!(record.average_response(2), None);
+    assert_eq!(record.tiebreak([0, 1, 2, 3]), [1, 0, 3, 2]);
+}
+
+#[test]
+fn perfect_bot_respects_targeting_rules() {
+    let skill = BotSkill { mistake_rate: 0.0, ..Default::default() };
+    let settings = GameSettings {
+        teams: 2,
+        no_teammate_targeting: true,
+        rules: TargetingRules { no_bounce_back: true, neighbours_only: false },
+        ..Default::default()
+    };
+    let players = vec![(0, Some(0)), (1, Some(1)), (2, Some(0)), (3, Some(1))];
+    let mut bot = Bot::new(0, skill, players, &settings, 0);
+    bot.observe(0, 1);
+    bot.observe(0, 0);
+    for _ in 0..32 {
+        let PlayerResponds { token, next, action, special } = bot.respond(0, "Zap".into());
+        assert_eq!(token, 0);
+        assert_eq!(next, 3);
+        assert_eq!(action, "Zap");
+        assert_eq!(special, None);
+    }
+
+    // Bouncing back is allowed with only two players left
+    bot.eliminate(3);
+    bot.eliminate(2);
+    assert_eq!(bot.respond(0, "Zip".into()).next, 1);
+}
+
+#[test]
+fn bot_strategies_pick_expected_targets() {
+    let skill = BotSkill { mistake_rate: 0.0, strategy: BotStrategy::Clockwise, ..Default::default() };
+    let settings =
+        GameSettings { rules: TargetingRules { neighbours_only: true, ..Default::default() }, ..Default::default() };
+    let players = vec![(0, None), (2, None), (5, None), (7, None)];
+    let mut bot = Bot::new(7, skill, players.clone(), &settings, 0);
+    assert_eq!(bot.respond(0, "Zip".into()).next, 0);
+    bot.eliminate(0);
+    assert_eq!(bot.respond(0, "Zip".into()).next, 2);
+
+    let skill = BotSkill { mistake_rate: 0.0, strategy: BotStrategy::Retaliate, ..Default::default() };
+    let mut bot = Bot::new(0, skill, players, &GameSettings::default(), 0);
+    bot.observe(0, 5);
+    bot.observe(0, 0);
+    assert_eq!(bot.respond(0, "Zip".into()).next, 5);
+    bot.lose_life(0);
+    assert_ne!(bot.respond(0, "Zip".into()).next, 0);
+}
+
+#[test]
+fn clumsy_bot_always_gets_action_wrong() {
+    let skill = BotSkill { reaction_time: 0.5, reaction_jitter: 0.0, mistake_rate: 1.0, ..Default::default() };
+    let players = vec![(0, None), (1, None)];
+    let mut bot = Bot::new(0, skill, players, &GameSettings::default(), 0);
+    assert_eq!(bot.reaction_time(), Duration::from_millis(500));
+    for _ in 0..32 {
+        let PlayerResponds { next, action, .. } = bot.respond(0, "Zop".into());
+        assert_eq!(next, 1);
+        assert_ne!(action, "Zop");
+    }
+}